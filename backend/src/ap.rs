@@ -1,6 +1,5 @@
 use activitypub_federation::{
-    activity_queue::queue_activity, config::Data, protocol::context::WithContext,
-    traits::ActivityHandler,
+    config::Data, protocol::context::WithContext, traits::ActivityHandler,
 };
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
@@ -8,20 +7,27 @@ use url::Url;
 
 use crate::{
     config::CONFIG,
+    entity::{activity, delivery},
     error::{Context, Error},
     state::State,
 };
 
 pub mod announce;
+pub mod collection;
 pub mod delete;
 pub mod flag;
 pub mod follow;
 pub mod like;
+pub mod r#move;
 pub mod note;
 pub mod other_activity;
+pub mod ownership;
 pub mod person;
+pub mod pin;
+pub mod relay;
 pub mod tag;
 pub mod undo;
+pub mod url_verifier;
 
 pub fn generate_object_id() -> Result<Url, Error> {
     Url::parse(&format!(
@@ -32,31 +38,79 @@ pub fn generate_object_id() -> Result<Url, Error> {
     .context_internal_server_error("failed to construct object URL")
 }
 
+/// Records the outgoing activity so that it can be fetched by its ID, then queues it for the
+/// inboxes. The queued deliveries are sent by the delivery worker.
+///
+/// `post_uri` is the post the activity is about, if the activity should be removed along with
+/// the post.
+#[tracing::instrument(skip(data))]
+pub async fn send_activity<A>(
+    activity: A,
+    post_uri: Option<&Url>,
+    inboxes: Vec<Url>,
+    data: &Data<State>,
+) -> Result<(), Error>
+where
+    A: ActivityHandler + Serialize + std::fmt::Debug + Send + Sync,
+{
+    let with_context = WithContext::new_default(activity);
+    activity::Model::record(
+        &with_context,
+        with_context.id(),
+        post_uri,
+        &inboxes,
+        &*data.db,
+    )
+    .await?;
+    delivery::Model::enqueue(&with_context, inboxes, &*data.db).await?;
+    Ok(())
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum NoteOrAnnounce {
-    Note(self::note::Note),
+    Note(Box<self::note::Note>),
     Announce(self::announce::Announce),
 }
 
 impl NoteOrAnnounce {
     #[tracing::instrument(skip(data))]
     pub async fn send(self, data: &Data<State>, inboxes: Vec<Url>) -> Result<(), Error> {
-        let me = self::person::LocalPerson::get(&*data.db).await?;
         match self {
             Self::Note(note) => {
-                let create_note = self::note::CreateNote::new(note)?;
-                let with_context = WithContext::new_default(create_note);
-                queue_activity(&with_context, &me, inboxes, data).await?;
-                Ok(())
+                let create_note = self::note::CreateNote::new(*note)?;
+                create_note.send(data, inboxes).await
             }
             Self::Announce(announce) => {
-                let with_context = WithContext::new_default(announce);
-                queue_activity(&with_context, &me, inboxes, data).await?;
-                Ok(())
+                let post_uri = announce.id.inner().clone();
+                send_activity(announce, Some(&post_uri), inboxes, data).await
             }
         }
     }
+
+    /// Wraps a note into the activity that would have delivered it.
+    ///
+    /// The activity ID of a wrapped note is derived from the note ID so it stays stable.
+    pub fn into_activity(self) -> Result<CreateOrAnnounce, Error> {
+        match self {
+            Self::Note(note) => {
+                let id = Url::parse(&format!("{}/activity", note.id.inner()))
+                    .context_internal_server_error("failed to construct activity URL")?;
+                Ok(CreateOrAnnounce::Create(Box::new(self::note::CreateNote {
+                    id,
+                    ..self::note::CreateNote::new(*note)?
+                })))
+            }
+            Self::Announce(announce) => Ok(CreateOrAnnounce::Announce(announce)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum CreateOrAnnounce {
+    Create(Box<self::note::CreateNote>),
+    Announce(self::announce::Announce),
 }
 
 #[derive(Deserialize, Serialize)]
@@ -66,13 +120,18 @@ pub enum Activity {
     AcceptFollow(self::follow::FollowAccept),
     Announce(self::announce::Announce),
     CreateFollow(self::follow::Follow),
-    CreateNote(self::note::CreateNote),
+    CreateNote(Box<self::note::CreateNote>),
     Delete(self::delete::Delete),
+    EmojiReact(self::like::EmojiReact),
     Flag(self::flag::Flag),
     Like(self::like::Like),
+    Move(self::r#move::Move),
     RejectFollow(self::follow::FollowReject),
+    UndoAnnounce(self::undo::Undo<self::announce::Announce>),
+    UndoEmojiReact(self::undo::Undo<self::like::EmojiReact>),
     UndoFollow(self::undo::Undo<self::follow::Follow>),
     UndoLike(self::undo::Undo<self::like::Like>),
+    UpdateNote(Box<self::note::UpdateNote>),
     UpdatePerson(Box<self::person::PersonUpdate>),
     /// Fallback
     Other(self::other_activity::OtherActivity),
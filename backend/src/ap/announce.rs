@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
-    entity::post,
+    entity::{blocked_instance, post, relay},
     error::{Context, Error},
     queue::{Event, Notification, NotificationType, Update},
     state::State,
@@ -31,7 +31,8 @@ pub struct Announce {
     pub id: ObjectId<post::Model>,
     #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
     pub actor: Url,
-    pub published: DateTime<FixedOffset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<DateTime<FixedOffset>>,
     #[derivative(Debug(format_with = "crate::fmt::debug_format_vec_display"))]
     #[serde(default)]
     pub to: Vec<Url>,
@@ -63,31 +64,60 @@ impl ActivityHandler for Announce {
 
     #[tracing::instrument(skip(data))]
     async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if relay::Model::find_by_actor(&self.actor, &*data.db)
+            .await?
+            .is_some()
+        {
+            // Relays announce posts of others, which are stored as they are instead of as reposts
+            let post = self.object.dereference(data).await?;
+            let event = Event::Update(Update::CreatePost {
+                post_id: post.id.into(),
+            });
+            event.send(&*data.db).await?;
+            return Ok(());
+        }
+
+        let actor = self.actor.clone();
         let post = post::Model::from_json(NoteOrAnnounce::Announce(self), data).await?;
+        receive_repost(&post, &actor, data).await
+    }
+}
 
-        let event = Event::Update(Update::CreatePost {
-            post_id: post.id.into(),
-        });
-        event.send(&*data.db).await?;
+/// Sends the events for a stored repost, notifying the local person if it reposts their post.
+#[tracing::instrument(skip(post, data))]
+pub async fn receive_repost(
+    post: &post::Model,
+    actor: &Url,
+    data: &Data<State>,
+) -> Result<(), Error> {
+    let silenced = blocked_instance::Model::is_silenced(actor, &*data.db).await?;
 
-        if let Some(repost_id) = post.repost_id {
-            let local_person_reposted_count = post::Entity::find_by_id(repost_id)
-                .filter(post::Column::UserId.is_null())
-                .count(&*data.db)
-                .await
-                .context_internal_server_error("failed to query database")?;
-            if local_person_reposted_count > 0 {
-                let event = Event::Notification(Notification::new(NotificationType::Reposted {
-                    user_id: post
-                        .user_id
-                        .context_internal_server_error("malformed user ID")?
-                        .into(),
-                    post_id: repost_id.into(),
-                }));
-                event.send(&*data.db).await?;
-            }
-        }
+    let event = Event::Update(Update::CreatePost {
+        post_id: post.id.into(),
+    });
+    event.send(&*data.db).await?;
 
-        Ok(())
+    if silenced {
+        return Ok(());
     }
+
+    if let Some(repost_id) = post.repost_id {
+        let local_person_reposted_count = post::Entity::find_by_id(repost_id)
+            .filter(post::Column::UserId.is_null())
+            .count(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        if local_person_reposted_count > 0 {
+            let event = Event::Notification(Notification::new(NotificationType::Reposted {
+                user_id: post
+                    .user_id
+                    .context_internal_server_error("malformed user ID")?
+                    .into(),
+                post_id: repost_id.into(),
+            }));
+            event.send(&*data.db).await?;
+        }
+    }
+
+    Ok(())
 }
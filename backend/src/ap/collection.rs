@@ -0,0 +1,45 @@
+use activitypub_federation::kinds::collection::{OrderedCollectionPageType, OrderedCollectionType};
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+#[derive(Clone, Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderedCollection<T> {
+    #[serde(rename = "type")]
+    pub ty: OrderedCollectionType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    pub total_items: u64,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first: Option<Url>,
+    #[derivative(Debug = "ignore")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ordered_items: Option<Vec<T>>,
+}
+
+#[derive(Clone, Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderedCollectionPage<T> {
+    #[serde(rename = "type")]
+    pub ty: OrderedCollectionPageType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub part_of: Url,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<Url>,
+    #[derivative(Debug = "ignore")]
+    pub ordered_items: Vec<T>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OrderedCollectionOrPage<T> {
+    Collection(OrderedCollection<T>),
+    Page(OrderedCollectionPage<T>),
+}
@@ -1,8 +1,7 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     kinds::{activity::DeleteType, object::TombstoneType},
-    protocol::{context::WithContext, verification::verify_domains_match},
+    protocol::verification::verify_domains_match,
     traits::ActivityHandler,
 };
 use async_trait::async_trait;
@@ -19,7 +18,7 @@ use crate::{
     state::State,
 };
 
-use super::{generate_object_id, person::LocalPerson};
+use super::{generate_object_id, ownership::verify_owner, person::LocalPerson, send_activity};
 
 #[derive(Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
@@ -59,10 +58,8 @@ impl Delete {
 
     #[tracing::instrument(skip(data))]
     pub async fn send(self, data: &Data<State>, inboxes: Vec<Url>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
-        let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, inboxes, data).await?;
-        Ok(())
+        let post_uri = self.object.id.clone();
+        send_activity(self, Some(&post_uri), inboxes, data).await
     }
 }
 
@@ -82,7 +79,8 @@ impl ActivityHandler for Delete {
     #[tracing::instrument(skip(_data))]
     async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
         verify_domains_match(&self.object.id, &self.id)
-            .context_bad_request("failed to verify domain")
+            .context_bad_request("failed to verify domain")?;
+        verify_domains_match(&self.actor, &self.id).context_bad_request("failed to verify domain")
     }
 
     #[tracing::instrument(skip(data))]
@@ -101,6 +99,8 @@ impl ActivityHandler for Delete {
             .await
             .context_internal_server_error("failed to query from database")?;
         if let Some(post) = post {
+            verify_owner(&self.actor, &post, &tx).await?;
+
             let post_id = post.id;
             ModelTrait::delete(post, &tx)
                 .await
@@ -124,6 +124,8 @@ impl ActivityHandler for Delete {
             .context_internal_server_error("failed to query from database")?;
 
         if let Some(user) = user {
+            verify_owner(&self.actor, &user, &tx).await?;
+
             let user_id = user.id;
             ModelTrait::delete(user, &tx)
                 .await
@@ -1,10 +1,6 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
-    config::Data,
-    fetch::object_id::ObjectId,
-    kinds::activity::FlagType,
-    protocol::{context::WithContext, verification::verify_domains_match},
-    traits::ActivityHandler,
+    config::Data, fetch::object_id::ObjectId, kinds::activity::FlagType,
+    protocol::verification::verify_domains_match, traits::ActivityHandler,
 };
 use async_trait::async_trait;
 use derivative::Derivative;
@@ -18,10 +14,11 @@ use crate::{
     entity::{report, user},
     error::{Context, Error},
     queue::{Event, Notification, NotificationType},
+    sanitize::sanitize_html,
     state::State,
 };
 
-use super::generate_object_id;
+use super::{generate_object_id, send_activity};
 
 #[derive(Clone, Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
@@ -50,10 +47,7 @@ impl Flag {
     }
 
     pub async fn send(self, data: &Data<State>, inbox: Url) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
-        let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, vec![inbox], data).await?;
-        Ok(())
+        send_activity(self, None, vec![inbox], data).await
     }
 }
 
@@ -83,7 +77,7 @@ impl ActivityHandler for Flag {
         let report_activemodel = report::ActiveModel {
             id: ActiveValue::Set(Ulid::new().into()),
             from_user_id: ActiveValue::Set(from_user.id),
-            content: ActiveValue::Set(self.content),
+            content: ActiveValue::Set(sanitize_html(&self.content)),
         };
         let report = report_activemodel
             .insert(&*data.db)
@@ -1,16 +1,18 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
-    kinds::activity::{AcceptType, FollowType, RejectType},
-    protocol::{context::WithContext, verification::verify_domains_match},
+    kinds::{
+        activity::{AcceptType, FollowType, RejectType},
+        public,
+    },
+    protocol::verification::verify_domains_match,
     traits::{ActivityHandler, Object},
 };
 use async_trait::async_trait;
 use derivative::Derivative;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QuerySelect,
-    TransactionTrait,
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter,
+    QuerySelect, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 use ulid::Ulid;
@@ -18,14 +20,14 @@ use url::Url;
 
 use crate::{
     config::CONFIG,
-    entity::{follow, follower, user},
+    entity::{blocked_instance, follow, follower, relay, user},
     error::{Context, Error},
     format_err,
     queue::{Event, Notification, NotificationType},
     state::State,
 };
 
-use super::{generate_object_id, person::LocalPerson};
+use super::{generate_object_id, person::LocalPerson, send_activity};
 
 #[derive(Clone, Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
@@ -42,14 +44,29 @@ pub struct Follow {
 }
 
 impl Follow {
+    /// Follows the public collection, which is how relays are subscribed to.
+    pub fn new_relay(relay_id: Ulid) -> Result<Self, Error> {
+        Ok(Self {
+            ty: Default::default(),
+            id: Some(relay::Model::follow_id(relay_id)?),
+            actor: LocalPerson::id(),
+            object: public(),
+        })
+    }
+
+    pub fn is_relay(&self) -> bool {
+        self.object == public()
+    }
+
     pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let object: ObjectId<user::Model> = self.object.clone().into();
         let inbox = object.dereference(data).await?.inbox;
         let inbox = Url::parse(&inbox).context_internal_server_error("malformed user inbox URL")?;
-        let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, vec![inbox], data).await?;
-        Ok(())
+        send_activity(self, None, vec![inbox], data).await
+    }
+
+    pub async fn send_relay(self, data: &Data<State>, inbox: Url) -> Result<(), Error> {
+        send_activity(self, None, vec![inbox], data).await
     }
 }
 
@@ -73,20 +90,36 @@ impl ActivityHandler for Follow {
 
     #[tracing::instrument(skip(data))]
     async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
-        let follower = follower::Model::from_json(self.clone(), data).await?;
+        let already_requested = follower::Entity::find()
+            .filter(follower::Column::Accepted.eq(false))
+            .inner_join(user::Entity)
+            .filter(user::Column::Uri.eq(self.actor.as_str()))
+            .count(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            > 0;
 
-        let accept = FollowAccept {
-            ty: Default::default(),
-            id: generate_object_id()?,
-            actor: LocalPerson::id(),
-            object: self,
-        };
-        accept.send(data).await?;
+        let follower = follower::Model::from_json(self.clone(), data).await?;
 
-        let event = Event::Notification(Notification::new(NotificationType::CreateFollower {
-            user_id: follower.from_id.into(),
-        }));
-        event.send(&*data.db).await?;
+        if follower.accepted {
+            let silenced = blocked_instance::Model::is_silenced(&self.actor, &*data.db).await?;
+
+            let accept = FollowAccept::new(self)?;
+            accept.send(data).await?;
+
+            if !silenced {
+                let event =
+                    Event::Notification(Notification::new(NotificationType::CreateFollower {
+                        user_id: follower.from_id.into(),
+                    }));
+                event.send(&*data.db).await?;
+            }
+        } else if !already_requested {
+            let event = Event::Notification(Notification::new(NotificationType::FollowRequest {
+                user_id: follower.from_id.into(),
+            }));
+            event.send(&*data.db).await?;
+        }
 
         Ok(())
     }
@@ -106,15 +139,21 @@ pub struct FollowAccept {
 }
 
 impl FollowAccept {
+    pub fn new(object: Follow) -> Result<Self, Error> {
+        Ok(Self {
+            ty: Default::default(),
+            id: generate_object_id()?,
+            actor: LocalPerson::id(),
+            object,
+        })
+    }
+
     #[tracing::instrument(skip(data))]
     pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let actor: ObjectId<user::Model> = self.object.actor.clone().into();
         let inbox = actor.dereference(data).await?.inbox;
         let inbox = Url::parse(&inbox).context_internal_server_error("malformed user inbox URL")?;
-        let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, vec![inbox], data).await?;
-        Ok(())
+        send_activity(self, None, vec![inbox], data).await
     }
 }
 
@@ -131,14 +170,24 @@ impl ActivityHandler for FollowAccept {
         &self.actor
     }
 
-    #[tracing::instrument(skip(_data))]
-    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
-        verify_domains_match(&self.actor, &self.object.object)
-            .context_bad_request("failed to verify domain")
+    #[tracing::instrument(skip(data))]
+    async fn verify(&self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if self.object.is_relay() {
+            relay::Model::find_by_follow(self.object.id(), &self.actor, &*data.db).await?;
+            Ok(())
+        } else {
+            verify_domains_match(&self.actor, &self.object.object)
+                .context_bad_request("failed to verify domain")
+        }
     }
 
     #[tracing::instrument(skip(data))]
     async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if self.object.is_relay() {
+            return relay::Model::set_accepted(self.object.id(), &self.actor, true, &*data.db)
+                .await;
+        }
+
         let follow_id: ObjectId<follow::Model> = self.object.id().clone().into();
         let follow = follow_id.dereference(data).await?;
         let mut follow_activemodel: follow::ActiveModel = follow.into();
@@ -172,30 +221,31 @@ pub struct FollowReject {
 
 impl FollowReject {
     pub fn new(user_id: Ulid, user_uri: Url) -> Result<Self, Error> {
+        Self::from_follow(Follow {
+            ty: Default::default(),
+            id: Some(
+                Url::parse(&format!(
+                    "https://{}/follower/{}",
+                    CONFIG.public_domain, user_id
+                ))
+                .context_internal_server_error("failed to construct URL")?,
+            ),
+            actor: user_uri,
+            object: LocalPerson::id(),
+        })
+    }
+
+    pub fn from_follow(object: Follow) -> Result<Self, Error> {
         Ok(Self {
             ty: Default::default(),
             id: generate_object_id()?,
             actor: LocalPerson::id(),
-            object: Follow {
-                ty: Default::default(),
-                id: Some(
-                    Url::parse(&format!(
-                        "https://{}/follower/{}",
-                        CONFIG.public_domain, user_id
-                    ))
-                    .context_internal_server_error("failed to construct URL")?,
-                ),
-                actor: user_uri,
-                object: LocalPerson::id(),
-            },
+            object,
         })
     }
 
     pub async fn send(self, data: &Data<State>, inbox: Url) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
-        let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, vec![inbox], data).await?;
-        Ok(())
+        send_activity(self, None, vec![inbox], data).await
     }
 }
 
@@ -219,6 +269,11 @@ impl ActivityHandler for FollowReject {
 
     #[tracing::instrument(skip(data))]
     async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        if self.object.is_relay() {
+            return relay::Model::set_accepted(self.object.id(), &self.actor, false, &*data.db)
+                .await;
+        }
+
         let follow_user_id = self.object.object;
 
         let tx = data
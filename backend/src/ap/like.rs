@@ -1,9 +1,8 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
-    kinds::activity::LikeType,
-    protocol::{context::WithContext, verification::verify_domains_match},
+    kinds::{activity::LikeType, kind},
+    protocol::verification::verify_domains_match,
     traits::{ActivityHandler, Object},
 };
 use async_trait::async_trait;
@@ -13,13 +12,13 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
-    entity::{post, reaction, user},
+    entity::{blocked_instance, instance, post, reaction, user},
     error::{Context, Error},
     queue::{Event, Notification, NotificationType, Update},
     state::State,
 };
 
-use super::{person::LocalPerson, tag::Tag};
+use super::{send_activity, tag::Tag, undo::Undo};
 
 #[derive(Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
@@ -35,14 +34,45 @@ pub struct Like {
     pub object: ObjectId<post::Model>,
     #[serde(default)]
     pub content: Option<String>,
+    /// Reaction used by Misskey, which older versions send instead of `content`.
+    #[serde(
+        rename = "_misskey_reaction",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub misskey_reaction: Option<String>,
     #[serde(default)]
     pub tag: Vec<Tag>,
 }
 
+/// Software that only understands emoji reactions sent as `EmojiReact`.
+const EMOJI_REACT_SOFTWARE: [&str; 2] = ["pleroma", "akkoma"];
+
 impl Like {
     #[tracing::instrument(skip(data))]
     pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
+        let (inbox, is_emoji_react) = self.target(data).await?;
+        if is_emoji_react {
+            send_activity(EmojiReact::from(self), None, vec![inbox], data).await
+        } else {
+            send_activity(self, None, vec![inbox], data).await
+        }
+    }
+
+    #[tracing::instrument(skip(data))]
+    pub async fn send_undo(self, data: &Data<State>) -> Result<(), Error> {
+        let (inbox, is_emoji_react) = self.target(data).await?;
+        if is_emoji_react {
+            let undo = Undo::new(EmojiReact::from(self))?;
+            undo.send(data, vec![inbox]).await
+        } else {
+            let undo = Undo::new(self)?;
+            undo.send(data, vec![inbox]).await
+        }
+    }
+
+    /// Returns the inbox of the post author, and whether the instance expects `EmojiReact`.
+    async fn target(&self, data: &Data<State>) -> Result<(Url, bool), Error> {
         let post = self.object.dereference(data).await?;
         let user = post
             .find_related(user::Entity)
@@ -52,9 +82,10 @@ impl Like {
             .context_internal_server_error("user not found")?;
         let inbox =
             Url::parse(&user.inbox).context_internal_server_error("malformed user inbox URL")?;
-        let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, vec![inbox], data).await?;
-        Ok(())
+        let software = instance::Model::software(&inbox, data).await?;
+        let is_emoji_react =
+            software.is_some_and(|software| EMOJI_REACT_SOFTWARE.contains(&software.as_str()));
+        Ok((inbox, is_emoji_react))
     }
 }
 
@@ -79,6 +110,7 @@ impl ActivityHandler for Like {
 
     #[tracing::instrument(skip(data))]
     async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        let silenced = blocked_instance::Model::is_silenced(&self.actor, &*data.db).await?;
         let reaction = reaction::Model::from_json(self, data).await?;
 
         let event = Event::Update(Update::CreateReaction {
@@ -86,6 +118,10 @@ impl ActivityHandler for Like {
         });
         event.send(&*data.db).await?;
 
+        if silenced {
+            return Ok(());
+        }
+
         let local_person_reacted_count = reaction
             .find_related(post::Entity)
             .filter(post::Column::UserId.is_null())
@@ -103,3 +139,75 @@ impl ActivityHandler for Like {
         Ok(())
     }
 }
+
+kind!(EmojiReactType, EmojiReact);
+
+/// Emoji reaction as sent by Pleroma and Akkoma. It is stored the same way as a `Like`.
+#[derive(Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EmojiReact {
+    #[serde(rename = "type")]
+    pub ty: EmojiReactType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: ObjectId<reaction::Model>,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub actor: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub object: ObjectId<post::Model>,
+    pub content: String,
+    #[serde(default)]
+    pub tag: Vec<Tag>,
+}
+
+impl From<EmojiReact> for Like {
+    fn from(value: EmojiReact) -> Self {
+        Self {
+            ty: Default::default(),
+            id: value.id,
+            actor: value.actor,
+            object: value.object,
+            content: Some(value.content),
+            misskey_reaction: None,
+            tag: value.tag,
+        }
+    }
+}
+
+impl From<Like> for EmojiReact {
+    fn from(value: Like) -> Self {
+        Self {
+            ty: Default::default(),
+            id: value.id,
+            actor: value.actor,
+            object: value.object,
+            content: value.content.or(value.misskey_reaction).unwrap_or_default(),
+            tag: value.tag,
+        }
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for EmojiReact {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        self.id.inner()
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(&self.actor, self.id.inner())
+            .context_bad_request("failed to verify domain")
+    }
+
+    #[tracing::instrument(skip(data))]
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        Like::from(self).receive(data).await
+    }
+}
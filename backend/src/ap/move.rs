@@ -0,0 +1,186 @@
+use activitypub_federation::{
+    config::Data,
+    fetch::object_id::ObjectId,
+    kinds::activity::MoveType,
+    protocol::verification::verify_domains_match,
+    traits::{ActivityHandler, Object},
+};
+use async_trait::async_trait;
+use derivative::Derivative;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, EntityTrait, ModelTrait, PaginatorTrait, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    entity::{follow, user},
+    error::{Context, Error},
+    format_err,
+    queue::{Event, Update},
+    state::State,
+    util::get_follower_inboxes,
+};
+
+use super::{
+    follow::Follow, generate_object_id, ownership::verify_is_owner, person::LocalPerson,
+    send_activity, undo::Undo,
+};
+
+#[derive(Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Move {
+    #[serde(rename = "type")]
+    pub ty: MoveType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub actor: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub object: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub target: Url,
+}
+
+impl Move {
+    pub fn new(target: Url) -> Result<Self, Error> {
+        Ok(Self {
+            ty: Default::default(),
+            id: generate_object_id()?,
+            actor: LocalPerson::id(),
+            object: LocalPerson::id(),
+            target,
+        })
+    }
+
+    #[tracing::instrument(skip(data))]
+    pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
+        let inboxes = get_follower_inboxes(&*data.db).await?;
+        send_activity(self, None, inboxes, data).await
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for Move {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(&self.id, &self.actor)
+            .context_bad_request("failed to verify domain")?;
+        verify_is_owner(&self.actor, &self.object)
+    }
+
+    #[tracing::instrument(skip(data))]
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        // The target must list the moving account as its alias
+        let target_aliases = if self.target == LocalPerson::id() {
+            LocalPerson::get(&*data.db).await?.also_known_as()
+        } else {
+            let target: ObjectId<user::Model> = self.target.clone().into();
+            target.dereference_forced(data).await?.also_known_as()
+        };
+        if !target_aliases.contains(&self.object) {
+            return Err(format_err!(
+                BAD_REQUEST,
+                "move target does not list the actor as its alias"
+            ));
+        }
+
+        let object: ObjectId<user::Model> = self.object.clone().into();
+        let user = object.dereference(data).await?;
+        let user_id = user.id;
+
+        let tx = data
+            .db
+            .begin()
+            .await
+            .context_internal_server_error("failed to begin database transaction")?;
+
+        let user_activemodel = user::ActiveModel {
+            id: ActiveValue::Unchanged(user_id),
+            moved_to: ActiveValue::Set(Some(self.target.to_string())),
+            ..Default::default()
+        };
+        user_activemodel
+            .update(&tx)
+            .await
+            .context_internal_server_error("failed to update database")?;
+
+        let existing_follow = follow::Entity::find_by_id(user_id)
+            .one(&tx)
+            .await
+            .context_internal_server_error("failed to query database")?;
+
+        // Moves to this instance do not need any follow to be re-targeted
+        let retarget = if self.target == LocalPerson::id() {
+            None
+        } else {
+            existing_follow
+        };
+
+        let (undo, new_follow) = if let Some(existing_follow) = retarget {
+            let target: ObjectId<user::Model> = self.target.clone().into();
+            let target = target.dereference(data).await?;
+
+            let undo = Undo::<Follow>::new(existing_follow.clone().into_json(data).await?)?;
+            ModelTrait::delete(existing_follow, &tx)
+                .await
+                .context_internal_server_error("failed to delete from database")?;
+
+            let target_follow_count = follow::Entity::find_by_id(target.id)
+                .count(&tx)
+                .await
+                .context_internal_server_error("failed to query database")?;
+            let new_follow = if target_follow_count == 0 {
+                let follow_activemodel = follow::ActiveModel {
+                    to_id: ActiveValue::Set(target.id),
+                    accepted: ActiveValue::Set(false),
+                };
+                Some(
+                    follow_activemodel
+                        .insert(&tx)
+                        .await
+                        .context_internal_server_error("failed to insert to database")?,
+                )
+            } else {
+                None
+            };
+
+            (Some(undo), new_follow)
+        } else {
+            (None, None)
+        };
+
+        tx.commit()
+            .await
+            .context_internal_server_error("failed to commit database transaction")?;
+
+        if let Some(undo) = undo {
+            let inbox = Url::parse(&user.inbox)
+                .context_internal_server_error("malformed user inbox URL")?;
+            undo.send(data, vec![inbox]).await?;
+        }
+        if let Some(new_follow) = new_follow {
+            let new_follow = new_follow.into_json(data).await?;
+            new_follow.send(data).await?;
+        }
+
+        let event = Event::Update(Update::UpdateUser {
+            user_id: user_id.into(),
+        });
+        event.send(&*data.db).await?;
+
+        Ok(())
+    }
+}
@@ -2,8 +2,9 @@ use activitypub_federation::{
     config::Data,
     fetch::object_id::ObjectId,
     kinds::{
-        activity::CreateType,
-        object::{DocumentType, NoteType},
+        activity::{CreateType, UpdateType},
+        collection::CollectionType,
+        object::DocumentType,
     },
     protocol::verification::verify_domains_match,
     traits::{ActivityHandler, Object},
@@ -12,18 +13,28 @@ use async_trait::async_trait;
 use chrono::{DateTime, FixedOffset};
 use derivative::Derivative;
 use mime::Mime;
-use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter};
+use sea_orm::{
+    ColumnTrait, EntityTrait, JoinType, ModelTrait, PaginatorTrait, QueryFilter, QuerySelect,
+    RelationTrait,
+};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
-    entity::{mention, post},
+    entity::{blocked_instance, mention, poll, post},
     error::{Context, Error},
     queue::{Event, Notification, NotificationType, Update},
     state::State,
 };
 
-use super::{generate_object_id, person::LocalPerson, tag::Tag, NoteOrAnnounce};
+use super::{
+    generate_object_id,
+    ownership::{verify_is_owner, verify_owner},
+    person::LocalPerson,
+    send_activity,
+    tag::Tag,
+    NoteOrAnnounce,
+};
 
 #[derive(Clone, Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
@@ -46,6 +57,32 @@ pub struct Source {
     pub media_type: Option<String>,
 }
 
+/// `Question` is a note with a poll attached.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub enum NoteType {
+    #[default]
+    Note,
+    Question,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PollOptionReplies {
+    #[serde(rename = "type")]
+    pub ty: CollectionType,
+    pub total_items: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PollOption {
+    #[serde(rename = "type")]
+    pub ty: NoteType,
+    pub name: String,
+    #[serde(default)]
+    pub replies: Option<PollOptionReplies>,
+}
+
 #[derive(Clone, Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
 #[serde(rename_all = "camelCase")]
@@ -59,7 +96,22 @@ pub struct Note {
     #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
     #[serde(default)]
     pub quote_url: Option<ObjectId<post::Model>>,
-    pub published: DateTime<FixedOffset>,
+    /// Quote target used by Misskey.
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(
+        rename = "_misskey_quote",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub misskey_quote: Option<ObjectId<post::Model>>,
+    /// Quote target used by Fedibird.
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_uri: Option<ObjectId<post::Model>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub updated: Option<DateTime<FixedOffset>>,
     #[derivative(Debug(format_with = "crate::fmt::debug_format_vec_display"))]
     #[serde(default)]
     pub to: Vec<Url>,
@@ -68,6 +120,7 @@ pub struct Note {
     pub cc: Vec<Url>,
     #[serde(default)]
     pub summary: Option<String>,
+    #[serde(default)]
     pub content: String,
     #[serde(default)]
     pub source: Option<Source>,
@@ -79,6 +132,61 @@ pub struct Note {
     pub sensitive: bool,
     #[serde(default)]
     pub tag: Vec<Tag>,
+    /// Set on poll votes, which are notes replying to the poll with the chosen option.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub one_of: Option<Vec<PollOption>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub any_of: Option<Vec<PollOption>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime<FixedOffset>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voters_count: Option<u64>,
+}
+
+impl Note {
+    /// Returns the quoted post, in any of the forms used by the implementations out there.
+    pub fn quote(&self) -> Option<ObjectId<post::Model>> {
+        self.quote_url
+            .clone()
+            .or_else(|| self.misskey_quote.clone())
+            .or_else(|| self.quote_uri.clone())
+            .or_else(|| {
+                self.tag.iter().find_map(|tag| match tag {
+                    Tag::Link(link) if link.is_object_link() => Some(link.href.clone().into()),
+                    _ => None,
+                })
+            })
+    }
+
+    /// Creates a vote for the option `name` of the poll `in_reply_to`.
+    pub fn new_vote(id: Url, name: String, in_reply_to: Url, poll_author: Url) -> Self {
+        Self {
+            ty: NoteType::Note,
+            id: id.into(),
+            attributed_to: LocalPerson::id(),
+            quote_url: None,
+            misskey_quote: None,
+            quote_uri: None,
+            published: None,
+            updated: None,
+            to: vec![poll_author],
+            cc: Vec::new(),
+            summary: None,
+            content: String::new(),
+            source: None,
+            in_reply_to: Some(in_reply_to.into()),
+            attachment: Vec::new(),
+            sensitive: false,
+            tag: Vec::new(),
+            name: Some(name),
+            one_of: None,
+            any_of: None,
+            end_time: None,
+            voters_count: None,
+        }
+    }
 }
 
 #[derive(Derivative, Deserialize, Serialize)]
@@ -111,6 +219,12 @@ impl CreateNote {
             object: note,
         })
     }
+
+    #[tracing::instrument(skip(data))]
+    pub async fn send(self, data: &Data<State>, inboxes: Vec<Url>) -> Result<(), Error> {
+        let post_uri = self.object.id.inner().clone();
+        send_activity(self, Some(&post_uri), inboxes, data).await
+    }
 }
 
 #[async_trait]
@@ -133,20 +247,56 @@ impl ActivityHandler for CreateNote {
 
     #[tracing::instrument(skip(data))]
     async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
-        let post = post::Model::from_json(NoteOrAnnounce::Note(self.object), data).await?;
+        if let (Some(name), Some(in_reply_to)) = (&self.object.name, &self.object.in_reply_to) {
+            let local_poll = poll::Entity::find()
+                .join(JoinType::InnerJoin, poll::Relation::Post.def())
+                .filter(post::Column::Uri.eq(in_reply_to.inner().as_str()))
+                .filter(post::Column::UserId.is_null())
+                .one(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")?;
+            if let Some(local_poll) = local_poll {
+                // Replies with a name to local polls are votes, not posts
+                return local_poll
+                    .receive_vote(&self.actor, self.object.id.inner(), name, data)
+                    .await;
+            }
+        }
+
+        let silenced = blocked_instance::Model::is_silenced(&self.actor, &*data.db).await?;
+        let post =
+            post::Model::from_json(NoteOrAnnounce::Note(Box::new(self.object)), data).await?;
 
         let event = Event::Update(Update::CreatePost {
             post_id: post.id.into(),
         });
         event.send(&*data.db).await?;
 
+        if silenced {
+            return Ok(());
+        }
+
+        let local_person_replied_count = if let Some(reply_id) = post.reply_id {
+            post::Entity::find_by_id(reply_id)
+                .filter(post::Column::UserId.is_null())
+                .count(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")?
+        } else {
+            0
+        };
         let local_person_mentioned_count = post
             .find_related(mention::Entity)
             .filter(mention::Column::UserUri.eq(LocalPerson::id().as_str()))
             .count(&*data.db)
             .await
             .context_internal_server_error("failed to query database")?;
-        if local_person_mentioned_count > 0 {
+        if local_person_replied_count > 0 {
+            let event = Event::Notification(Notification::new(NotificationType::Replied {
+                post_id: post.id.into(),
+            }));
+            event.send(&*data.db).await?;
+        } else if local_person_mentioned_count > 0 {
             let event = Event::Notification(Notification::new(NotificationType::Mentioned {
                 post_id: post.id.into(),
             }));
@@ -170,3 +320,84 @@ impl ActivityHandler for CreateNote {
         Ok(())
     }
 }
+
+#[derive(Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateNote {
+    #[serde(rename = "type")]
+    pub ty: UpdateType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub actor: Url,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_vec_display"))]
+    #[serde(default)]
+    pub to: Vec<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_vec_display"))]
+    #[serde(default)]
+    pub cc: Vec<Url>,
+    pub object: Note,
+}
+
+impl UpdateNote {
+    pub fn new(note: Note) -> Result<Self, Error> {
+        Ok(Self {
+            ty: Default::default(),
+            id: generate_object_id()?,
+            actor: note.attributed_to.clone(),
+            to: note.to.clone(),
+            cc: note.cc.clone(),
+            object: note,
+        })
+    }
+
+    #[tracing::instrument(skip(data))]
+    pub async fn send(self, data: &Data<State>, inboxes: Vec<Url>) -> Result<(), Error> {
+        let post_uri = self.object.id.inner().clone();
+        send_activity(self, Some(&post_uri), inboxes, data).await
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for UpdateNote {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(&self.id, &self.actor)
+            .context_bad_request("failed to verify domain")?;
+        verify_domains_match(self.object.id.inner(), &self.actor)
+            .context_bad_request("failed to verify domain")?;
+        verify_is_owner(&self.actor, &self.object.attributed_to)
+    }
+
+    #[tracing::instrument(skip(data))]
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        let existing = post::Model::read_from_id(self.object.id.inner().clone(), data).await?;
+
+        if let Some(existing) = existing {
+            verify_owner(&self.actor, &existing, &*data.db).await?;
+
+            let post =
+                post::Model::from_json(NoteOrAnnounce::Note(Box::new(self.object)), data).await?;
+
+            let event = Event::Update(Update::UpdatePost {
+                post_id: post.id.into(),
+            });
+            event.send(&*data.db).await?;
+        }
+
+        // Updates of the posts never seen before are ignored
+        Ok(())
+    }
+}
@@ -0,0 +1,323 @@
+use async_trait::async_trait;
+use sea_orm::{ConnectionTrait, EntityTrait, QuerySelect};
+use url::Url;
+
+use crate::{
+    entity::{post, reaction, user},
+    error::{Context, Error},
+    format_err,
+};
+
+use super::person::LocalPerson;
+
+/// Objects that only their owner is allowed to update, delete or undo.
+#[async_trait]
+pub trait Owned {
+    /// Returns the URI of the actor who owns this object.
+    async fn owner<C>(&self, db: &C) -> Result<Url, Error>
+    where
+        C: ConnectionTrait;
+}
+
+/// Returns an error if `actor` is not `owner`.
+pub fn verify_is_owner(actor: &Url, owner: &Url) -> Result<(), Error> {
+    if actor == owner {
+        Ok(())
+    } else {
+        Err(format_err!(
+            FORBIDDEN,
+            "actor is not the owner of the object"
+        ))
+    }
+}
+
+/// Returns an error if `actor` does not own `object`.
+#[tracing::instrument(skip(object, db))]
+pub async fn verify_owner<T, C>(actor: &Url, object: &T, db: &C) -> Result<(), Error>
+where
+    T: Owned + Sync,
+    C: ConnectionTrait,
+{
+    let owner = object.owner(db).await?;
+    verify_is_owner(actor, &owner)
+}
+
+async fn user_uri_or_local<C>(user_id: Option<uuid::Uuid>, db: &C) -> Result<Url, Error>
+where
+    C: ConnectionTrait,
+{
+    if let Some(user_id) = user_id {
+        let user_uri = user::Entity::find_by_id(user_id)
+            .select_only()
+            .column(user::Column::Uri)
+            .into_tuple::<String>()
+            .one(db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .context_internal_server_error("failed to find user")?;
+        Url::parse(&user_uri).context_internal_server_error("malformed user URI")
+    } else {
+        Ok(LocalPerson::id())
+    }
+}
+
+#[async_trait]
+impl Owned for post::Model {
+    async fn owner<C>(&self, db: &C) -> Result<Url, Error>
+    where
+        C: ConnectionTrait,
+    {
+        user_uri_or_local(self.user_id, db).await
+    }
+}
+
+#[async_trait]
+impl Owned for reaction::Model {
+    async fn owner<C>(&self, db: &C) -> Result<Url, Error>
+    where
+        C: ConnectionTrait,
+    {
+        user_uri_or_local(self.user_id, db).await
+    }
+}
+
+#[async_trait]
+impl Owned for user::Model {
+    async fn owner<C>(&self, _db: &C) -> Result<Url, Error>
+    where
+        C: ConnectionTrait,
+    {
+        Url::parse(&self.uri).context_internal_server_error("malformed user URI")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc};
+
+    use activitypub_federation::{
+        config::{Data, FederationConfig},
+        traits::ActivityHandler,
+    };
+    use axum::http::StatusCode;
+    use chrono::Utc;
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, Value};
+    use serde_json::json;
+    use sqlx::postgres::PgPoolOptions;
+    use stopper::Stopper;
+
+    use crate::{
+        ap::{delete::Delete, follow::Follow, like::Like, note::UpdateNote, undo::Undo},
+        entity::sea_orm_active_enums::Visibility,
+        state::State,
+    };
+
+    use super::*;
+
+    const ALICE: &str = "https://remote.example/users/alice";
+    const MALLORY: &str = "https://remote.example/users/mallory";
+    const NOTE: &str = "https://remote.example/notes/1";
+
+    async fn data(db: DatabaseConnection) -> Data<State> {
+        let state = State {
+            db: Arc::new(db),
+            db_pool: PgPoolOptions::new()
+                .connect_lazy("postgres://localhost")
+                .unwrap(),
+            http_client: reqwest::Client::new(),
+            stopper: Stopper::new(),
+        };
+        FederationConfig::builder()
+            .domain("chamsae.example")
+            .app_data(state)
+            .build()
+            .await
+            .unwrap()
+            .to_request_data()
+    }
+
+    fn owner_row(owner: &str) -> BTreeMap<&'static str, Value> {
+        BTreeMap::from([("uri", Value::from(owner))])
+    }
+
+    fn assert_forbidden(result: Result<(), Error>) {
+        let error = result.expect_err("non-owner must be rejected");
+        assert_eq!(error.status_code, StatusCode::FORBIDDEN);
+    }
+
+    fn post() -> post::Model {
+        post::Model {
+            id: uuid::Uuid::new_v4(),
+            created_at: Utc::now().fixed_offset(),
+            reply_id: None,
+            text: String::new(),
+            title: None,
+            user_id: Some(uuid::Uuid::new_v4()),
+            visibility: Visibility::Public,
+            is_sensitive: false,
+            uri: NOTE.to_string(),
+            repost_id: None,
+            source_content: None,
+            source_media_type: None,
+            updated_at: None,
+            pinned_at: None,
+        }
+    }
+
+    fn user(uri: &str) -> user::Model {
+        user::Model {
+            id: uuid::Uuid::new_v4(),
+            last_fetched_at: Utc::now().fixed_offset(),
+            handle: "alice".to_string(),
+            name: None,
+            host: "remote.example".to_string(),
+            inbox: format!("{uri}/inbox"),
+            public_key: String::new(),
+            uri: uri.to_string(),
+            avatar_url: None,
+            banner_url: None,
+            shared_inbox: None,
+            manually_approves_followers: false,
+            is_bot: false,
+            description: None,
+            also_known_as: json!([]),
+            moved_to: None,
+            gone_at: None,
+        }
+    }
+
+    fn reaction(uri: &str) -> reaction::Model {
+        reaction::Model {
+            id: uuid::Uuid::new_v4(),
+            user_id: Some(uuid::Uuid::new_v4()),
+            post_id: uuid::Uuid::new_v4(),
+            content: "❤".to_string(),
+            uri: uri.to_string(),
+            emoji_uri: None,
+            emoji_media_type: None,
+            emoji_image_url: None,
+        }
+    }
+
+    fn undo_like(actor: &str, like_actor: &str) -> Undo<Like> {
+        serde_json::from_value(json!({
+            "type": "Undo",
+            "id": "https://remote.example/undo/1",
+            "actor": actor,
+            "object": {
+                "type": "Like",
+                "id": "https://remote.example/likes/1",
+                "actor": like_actor,
+                "object": "https://chamsae.example/note/1",
+            },
+        }))
+        .unwrap()
+    }
+
+    fn update_note(actor: &str) -> UpdateNote {
+        serde_json::from_value(json!({
+            "type": "Update",
+            "id": "https://remote.example/updates/1",
+            "actor": actor,
+            "object": {
+                "type": "Note",
+                "id": NOTE,
+                "attributedTo": actor,
+                "content": "edited",
+            },
+        }))
+        .unwrap()
+    }
+
+    fn delete(actor: &str, object: &str) -> Delete {
+        serde_json::from_value(json!({
+            "type": "Delete",
+            "id": "https://remote.example/deletes/1",
+            "actor": actor,
+            "object": {
+                "type": "Tombstone",
+                "id": object,
+            },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn undo_follow_by_non_owner_is_rejected() {
+        let undo: Undo<Follow> = serde_json::from_value(json!({
+            "type": "Undo",
+            "id": "https://remote.example/undo/1",
+            "actor": MALLORY,
+            "object": {
+                "type": "Follow",
+                "id": "https://remote.example/follows/1",
+                "actor": ALICE,
+                "object": "https://chamsae.example/person",
+            },
+        }))
+        .unwrap();
+        let data = data(MockDatabase::new(DatabaseBackend::Postgres).into_connection()).await;
+
+        assert_forbidden(undo.verify(&data).await);
+    }
+
+    #[tokio::test]
+    async fn undo_like_by_non_owner_is_rejected() {
+        let data = data(MockDatabase::new(DatabaseBackend::Postgres).into_connection()).await;
+
+        assert_forbidden(undo_like(MALLORY, ALICE).verify(&data).await);
+        assert!(undo_like(ALICE, ALICE).verify(&data).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn undo_like_with_forged_actor_is_rejected() {
+        // The embedded actor matches, but the stored reaction belongs to someone else
+        let undo = undo_like(MALLORY, MALLORY);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[reaction(undo.object.id.inner().as_str())]])
+            .append_query_results([[owner_row(ALICE)]])
+            .into_connection();
+
+        assert_forbidden(undo.receive(&data(db).await).await);
+    }
+
+    #[tokio::test]
+    async fn delete_of_someone_elses_post_is_rejected() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[post()]])
+            .append_query_results([[owner_row(ALICE)]])
+            .into_connection();
+
+        assert_forbidden(delete(MALLORY, NOTE).receive(&data(db).await).await);
+    }
+
+    #[tokio::test]
+    async fn delete_of_another_user_is_rejected() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([Vec::<post::Model>::new()])
+            .append_query_results([[user(ALICE)]])
+            .into_connection();
+
+        assert_forbidden(delete(MALLORY, ALICE).receive(&data(db).await).await);
+    }
+
+    #[tokio::test]
+    async fn update_of_someone_elses_note_is_rejected() {
+        let mut update = update_note(MALLORY);
+        update.object.attributed_to = Url::parse(ALICE).unwrap();
+        let data = data(MockDatabase::new(DatabaseBackend::Postgres).into_connection()).await;
+
+        assert_forbidden(update.verify(&data).await);
+    }
+
+    #[tokio::test]
+    async fn update_with_forged_attribution_is_rejected() {
+        // The note claims to be Mallory's, but the stored post belongs to someone else
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[post()]])
+            .append_query_results([[owner_row(ALICE)]])
+            .into_connection();
+
+        assert_forbidden(update_note(MALLORY).receive(&data(db).await).await);
+    }
+}
@@ -1,9 +1,10 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     fetch::object_id::ObjectId,
     kinds::{activity::UpdateType, object::ImageType, public},
-    protocol::{context::WithContext, public_key::PublicKey, verification::verify_domains_match},
+    protocol::{
+        helpers::deserialize_one_or_many, public_key::PublicKey, verification::verify_domains_match,
+    },
     traits::{ActivityHandler, Actor, Object},
 };
 use async_trait::async_trait;
@@ -23,7 +24,7 @@ use crate::{
     util::get_follower_inboxes,
 };
 
-use super::generate_object_id;
+use super::{generate_object_id, ownership::verify_is_owner, send_activity};
 
 #[derive(Clone, Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
@@ -74,11 +75,48 @@ pub struct Person {
     #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
     #[serde(default)]
     pub shared_inbox: Option<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default)]
+    pub outbox: Option<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default)]
+    pub followers: Option<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default)]
+    pub following: Option<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default)]
+    pub featured: Option<Url>,
     #[serde(default)]
     pub manually_approves_followers: bool,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_vec_display"))]
+    #[serde(default, deserialize_with = "deserialize_one_or_many")]
+    pub also_known_as: Vec<Url>,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[serde(default)]
+    pub moved_to: Option<Url>,
     pub public_key: PublicKey,
 }
 
+impl Person {
+    /// Strips everything but what is needed to verify signatures and deliver activities.
+    pub fn into_restricted(self) -> Self {
+        Self {
+            name: None,
+            summary: None,
+            icon: None,
+            image: None,
+            outbox: None,
+            followers: None,
+            following: None,
+            featured: None,
+            also_known_as: Vec::new(),
+            moved_to: None,
+            ..self
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct LocalPerson(pub setting::Model);
 
@@ -95,6 +133,10 @@ impl LocalPerson {
         &self.0.user_description
     }
 
+    pub fn also_known_as(&self) -> Vec<Url> {
+        serde_json::from_value(self.0.user_also_known_as.clone()).unwrap_or_default()
+    }
+
     pub async fn get_avatar_url(&self, db: &impl ConnectionTrait) -> Result<Option<Url>, Error> {
         if let Some(file_id) = self.0.avatar_file_id {
             let url = local_file::Entity::find_by_id(file_id)
@@ -136,6 +178,21 @@ impl LocalPerson {
             .context_internal_server_error("failed to construct followers URL")
     }
 
+    pub fn following() -> Result<Url, Error> {
+        Url::parse(&format!("{}/following", Self::id()))
+            .context_internal_server_error("failed to construct following URL")
+    }
+
+    pub fn featured() -> Result<Url, Error> {
+        Url::parse(&format!("{}/featured", Self::id()))
+            .context_internal_server_error("failed to construct featured URL")
+    }
+
+    pub fn outbox() -> Result<Url, Error> {
+        Url::parse(&format!("{}/outbox", Self::id()))
+            .context_internal_server_error("failed to construct outbox URL")
+    }
+
     pub fn id() -> Url {
         static ID: Lazy<Url> = Lazy::new(|| {
             Url::parse(&format!("https://{}/person", CONFIG.public_domain))
@@ -196,12 +253,24 @@ impl Object for LocalPerson {
                 }),
             inbox: self.inbox(),
             shared_inbox: Some(self.inbox()),
+            outbox: Some(Self::outbox()?),
+            followers: Some(Self::followers()?),
+            following: Some(Self::following()?),
+            featured: Some(Self::featured()?),
             public_key: PublicKey {
                 id: format!("{}#main-key", id),
                 owner: id,
                 public_key_pem: self.public_key_pem().to_string(),
             },
-            manually_approves_followers: false,
+            manually_approves_followers: self.0.manually_approves_followers,
+            also_known_as: self.also_known_as(),
+            moved_to: self
+                .0
+                .user_moved_to
+                .as_deref()
+                .map(Url::parse)
+                .transpose()
+                .context_internal_server_error("malformed moved to URI")?,
             name: self.0.user_name,
             summary: self.0.user_description,
         })
@@ -274,11 +343,8 @@ impl PersonUpdate {
     }
 
     pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
         let inboxes = get_follower_inboxes(&*data.db).await?;
-        let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, inboxes, data).await?;
-        Ok(())
+        send_activity(self, None, inboxes, data).await
     }
 }
 
@@ -298,7 +364,8 @@ impl ActivityHandler for PersonUpdate {
     #[tracing::instrument(skip(_data))]
     async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
         verify_domains_match(&self.id, self.object.id.inner())
-            .context_bad_request("failed to verify domain")
+            .context_bad_request("failed to verify domain")?;
+        verify_is_owner(&self.actor, self.object.id.inner())
     }
 
     #[tracing::instrument(skip(data))]
@@ -0,0 +1,143 @@
+use activitypub_federation::{
+    config::Data,
+    kinds::activity::{AddType, RemoveType},
+    protocol::verification::verify_domains_match,
+    traits::ActivityHandler,
+};
+use async_trait::async_trait;
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    error::{Context, Error},
+    format_err,
+    state::State,
+    util::get_follower_inboxes,
+};
+
+use super::{generate_object_id, person::LocalPerson, send_activity};
+
+#[derive(Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Add {
+    #[serde(rename = "type")]
+    pub ty: AddType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub actor: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub object: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub target: Url,
+}
+
+impl Add {
+    /// Adds the post to the featured collection of the local person.
+    pub fn new_pin(object: Url) -> Result<Self, Error> {
+        Ok(Self {
+            ty: Default::default(),
+            id: generate_object_id()?,
+            actor: LocalPerson::id(),
+            object,
+            target: LocalPerson::featured()?,
+        })
+    }
+
+    #[tracing::instrument(skip(data))]
+    pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
+        let inboxes = get_follower_inboxes(&*data.db).await?;
+        let post_uri = self.object.clone();
+        send_activity(self, Some(&post_uri), inboxes, data).await
+    }
+}
+
+/// Only implemented to satisfy the bound of `send_activity`, as receiving is not supported.
+#[async_trait]
+impl ActivityHandler for Add {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(&self.id, &self.actor).context_bad_request("failed to verify domain")
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn receive(self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        // Featured collections of remote users are not tracked
+        Err(format_err!(NOT_IMPLEMENTED, "unimplemented activity"))
+    }
+}
+
+#[derive(Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Remove {
+    #[serde(rename = "type")]
+    pub ty: RemoveType,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub actor: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub object: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub target: Url,
+}
+
+impl Remove {
+    /// Removes the post from the featured collection of the local person.
+    pub fn new_unpin(object: Url) -> Result<Self, Error> {
+        Ok(Self {
+            ty: Default::default(),
+            id: generate_object_id()?,
+            actor: LocalPerson::id(),
+            object,
+            target: LocalPerson::featured()?,
+        })
+    }
+
+    #[tracing::instrument(skip(data))]
+    pub async fn send(self, data: &Data<State>) -> Result<(), Error> {
+        let inboxes = get_follower_inboxes(&*data.db).await?;
+        let post_uri = self.object.clone();
+        send_activity(self, Some(&post_uri), inboxes, data).await
+    }
+}
+
+/// Only implemented to satisfy the bound of `send_activity`, as receiving is not supported.
+#[async_trait]
+impl ActivityHandler for Remove {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(&self.id, &self.actor).context_bad_request("failed to verify domain")
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn receive(self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        // Featured collections of remote users are not tracked
+        Err(format_err!(NOT_IMPLEMENTED, "unimplemented activity"))
+    }
+}
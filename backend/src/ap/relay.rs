@@ -0,0 +1,78 @@
+use activitypub_federation::{
+    config::Data, fetch::object_id::ObjectId, protocol::verification::verify_domains_match,
+};
+use derivative::Derivative;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    ap::announce::receive_repost,
+    entity::post,
+    error::{Context, Error},
+    queue::{Event, Update},
+    state::State,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum RelayedObject {
+    Id(Url),
+    Object { id: Url },
+}
+
+impl RelayedObject {
+    pub fn id(&self) -> &Url {
+        match self {
+            Self::Id(id) => id,
+            Self::Object { id } => id,
+        }
+    }
+}
+
+/// An activity of another actor forwarded by a relay.
+///
+/// Relays sign forwarded activities with their own key instead of the key of the actor, so the
+/// object is fetched from its origin rather than trusting the forwarded copy.
+#[derive(Derivative, Deserialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RelayedActivity {
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub id: Url,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub actor: Url,
+    pub object: RelayedObject,
+}
+
+impl RelayedActivity {
+    #[tracing::instrument(skip(data))]
+    pub async fn receive(self, data: &Data<State>) -> Result<(), Error> {
+        match self.ty.as_str() {
+            "Create" => {
+                let object_id = self.object.id();
+                verify_domains_match(object_id, &self.actor)
+                    .context_bad_request("failed to verify domain")?;
+                let object: ObjectId<post::Model> = object_id.clone().into();
+                let post = object.dereference(data).await?;
+
+                let event = Event::Update(Update::CreatePost {
+                    post_id: post.id.into(),
+                });
+                event.send(&*data.db).await?;
+            }
+            "Announce" => {
+                // Reposts are stored as posts too, so the announce itself is fetched
+                verify_domains_match(&self.id, &self.actor)
+                    .context_bad_request("failed to verify domain")?;
+                let announce: ObjectId<post::Model> = self.id.into();
+                let post = announce.dereference(data).await?;
+                receive_repost(&post, &self.actor, data).await?;
+            }
+            _ => tracing::debug!("ignoring relayed activity"),
+        }
+
+        Ok(())
+    }
+}
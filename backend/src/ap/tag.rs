@@ -1,4 +1,7 @@
-use activitypub_federation::kinds::{link::MentionType, object::ImageType};
+use activitypub_federation::kinds::{
+    link::{LinkType, MentionType},
+    object::ImageType,
+};
 use derivative::Derivative;
 use mime::Mime;
 use serde::{Deserialize, Serialize};
@@ -69,6 +72,13 @@ pub struct EmojiIcon {
     pub url: Url,
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmojiLicense {
+    #[serde(default)]
+    pub free_text: Option<String>,
+}
+
 #[derive(Clone, Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
 #[serde(rename_all = "camelCase")]
@@ -79,6 +89,51 @@ pub struct Emoji {
     pub id: Url,
     pub name: String,
     pub icon: EmojiIcon,
+    #[serde(
+        rename = "_misskey_license",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub license: Option<EmojiLicense>,
+}
+
+/// Media type of links to ActivityPub objects, as used by FEP-e232 object links.
+pub const ACTIVITY_STREAMS_MEDIA_TYPE: &str =
+    "application/ld+json; profile=\"https://www.w3.org/ns/activitystreams\"";
+
+#[derive(Clone, Derivative, Deserialize, Serialize)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Link {
+    #[serde(rename = "type")]
+    pub ty: LinkType,
+    #[serde(default)]
+    pub media_type: Option<String>,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    pub href: Url,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl Link {
+    /// Creates a FEP-e232 object link to the quoted post.
+    pub fn new_quote(href: Url) -> Self {
+        Self {
+            ty: Default::default(),
+            media_type: Some(ACTIVITY_STREAMS_MEDIA_TYPE.to_string()),
+            name: Some(format!("RE: {}", href)),
+            href,
+        }
+    }
+
+    /// Returns whether the link points to an ActivityPub object rather than a web page.
+    pub fn is_object_link(&self) -> bool {
+        self.media_type.as_deref().is_some_and(|media_type| {
+            media_type == "application/activity+json"
+                || (media_type.starts_with("application/ld+json")
+                    && media_type.contains("https://www.w3.org/ns/activitystreams"))
+        })
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -87,4 +142,5 @@ pub enum Tag {
     Mention(Mention),
     Hashtag(Hashtag),
     Emoji(Emoji),
+    Link(Link),
 }
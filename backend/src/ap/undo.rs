@@ -1,27 +1,35 @@
 use activitypub_federation::{
-    activity_queue::queue_activity,
     config::Data,
     kinds::activity::UndoType,
-    protocol::{context::WithContext, verification::verify_domains_match},
+    protocol::verification::verify_domains_match,
     traits::{ActivityHandler, Object},
 };
 use async_trait::async_trait;
 use derivative::Derivative;
 use sea_orm::{
-    ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect, TransactionTrait,
+    ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QuerySelect,
+    TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::{
-    entity::{follower, user},
+    entity::{follower, post, user},
     error::{Context, Error},
     format_err,
-    queue::{Event, Notification, NotificationType},
+    queue::{Event, Notification, NotificationType, Update},
     state::State,
 };
 
-use super::{follow::Follow, generate_object_id, like::Like, person::LocalPerson};
+use super::{
+    announce::Announce,
+    follow::Follow,
+    generate_object_id,
+    like::{EmojiReact, Like},
+    ownership::{verify_is_owner, verify_owner},
+    person::LocalPerson,
+    send_activity,
+};
 
 #[derive(Derivative, Deserialize, Serialize)]
 #[derivative(Debug)]
@@ -49,15 +57,43 @@ impl<T> Undo<T> {
 
 impl<T> Undo<T>
 where
-    T: std::fmt::Debug + Serialize + Send + Sync + 'static,
+    T: Undoable + std::fmt::Debug + Serialize + Send + Sync + 'static,
     Undo<T>: ActivityHandler<DataType = State, Error = Error>,
 {
     #[tracing::instrument(skip(data))]
     pub async fn send(self, data: &Data<State>, inboxes: Vec<Url>) -> Result<(), Error> {
-        let me = LocalPerson::get(&*data.db).await?;
-        let with_context = WithContext::new_default(self);
-        queue_activity(&with_context, &me, inboxes, data).await?;
-        Ok(())
+        let post_uri = self.object.post_uri().cloned();
+        send_activity(self, post_uri.as_ref(), inboxes, data).await
+    }
+}
+
+/// Activities that can be undone.
+pub trait Undoable {
+    /// Returns the URI of the post the activity is about, if any.
+    fn post_uri(&self) -> Option<&Url>;
+}
+
+impl Undoable for Follow {
+    fn post_uri(&self) -> Option<&Url> {
+        None
+    }
+}
+
+impl Undoable for Like {
+    fn post_uri(&self) -> Option<&Url> {
+        Some(self.object.inner())
+    }
+}
+
+impl Undoable for EmojiReact {
+    fn post_uri(&self) -> Option<&Url> {
+        Some(self.object.inner())
+    }
+}
+
+impl Undoable for Announce {
+    fn post_uri(&self) -> Option<&Url> {
+        Some(self.id.inner())
     }
 }
 
@@ -77,7 +113,10 @@ impl ActivityHandler for Undo<Follow> {
     #[tracing::instrument(skip(_data))]
     async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
         verify_domains_match(self.object.id(), &self.id)
-            .context_bad_request("failed to verify domain")
+            .context_bad_request("failed to verify domain")?;
+        verify_domains_match(&self.actor, &self.id)
+            .context_bad_request("failed to verify domain")?;
+        verify_is_owner(&self.actor, &self.object.actor)
     }
 
     #[tracing::instrument(skip(data))]
@@ -140,7 +179,10 @@ impl ActivityHandler for Undo<Like> {
     #[tracing::instrument(skip(_data))]
     async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
         verify_domains_match(self.object.id(), &self.id)
-            .context_bad_request("failed to verify domain")
+            .context_bad_request("failed to verify domain")?;
+        verify_domains_match(&self.actor, &self.id)
+            .context_bad_request("failed to verify domain")?;
+        verify_is_owner(&self.actor, &self.object.actor)
     }
 
     #[tracing::instrument(skip(data))]
@@ -148,7 +190,8 @@ impl ActivityHandler for Undo<Like> {
         let res = self.object.id.dereference_local(data).await;
         match res {
             Ok(object) => {
-                object.delete(data).await?;
+                verify_owner(&self.actor, &object, &*data.db).await?;
+                Object::delete(object, data).await?;
                 Ok(())
             }
             Err(error) => {
@@ -165,3 +208,83 @@ impl ActivityHandler for Undo<Like> {
         }
     }
 }
+
+#[async_trait]
+impl ActivityHandler for Undo<EmojiReact> {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(self.object.id(), &self.id)
+            .context_bad_request("failed to verify domain")?;
+        verify_domains_match(&self.actor, &self.id)
+            .context_bad_request("failed to verify domain")?;
+        verify_is_owner(&self.actor, &self.object.actor)
+    }
+
+    #[tracing::instrument(skip(data))]
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        // Emoji reactions are stored as likes, so they are undone the same way
+        let undo = Undo::<Like> {
+            ty: self.ty,
+            id: self.id,
+            actor: self.actor,
+            object: self.object.into(),
+        };
+        undo.receive(data).await
+    }
+}
+
+#[async_trait]
+impl ActivityHandler for Undo<Announce> {
+    type DataType = State;
+    type Error = Error;
+
+    fn id(&self) -> &Url {
+        &self.id
+    }
+
+    fn actor(&self) -> &Url {
+        &self.actor
+    }
+
+    #[tracing::instrument(skip(_data))]
+    async fn verify(&self, _data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        verify_domains_match(self.object.id.inner(), &self.id)
+            .context_bad_request("failed to verify domain")?;
+        verify_domains_match(&self.actor, &self.id)
+            .context_bad_request("failed to verify domain")?;
+        verify_is_owner(&self.actor, &self.object.actor)
+    }
+
+    #[tracing::instrument(skip(data))]
+    async fn receive(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
+        let post = post::Model::read_from_id(self.object.id.into_inner(), data).await?;
+
+        if let Some(post) = post {
+            verify_owner(&self.actor, &post, &*data.db).await?;
+
+            let post_id = post.id;
+            ModelTrait::delete(post, &*data.db)
+                .await
+                .context_internal_server_error("failed to delete from database")?;
+
+            let event = Event::Update(Update::DeletePost {
+                post_id: post_id.into(),
+            });
+            event.send(&*data.db).await?;
+        }
+
+        // Undoing the reposts never seen before are ignored
+        Ok(())
+    }
+}
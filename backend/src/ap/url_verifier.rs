@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use activitypub_federation::config::UrlVerifier;
+use async_trait::async_trait;
+use sea_orm::DatabaseConnection;
+use url::Url;
+
+use crate::entity::blocked_instance;
+
+/// Rejects every URL on a suspended instance, both when fetching and when delivering.
+#[derive(Clone)]
+pub struct BlockedInstanceVerifier {
+    db: Arc<DatabaseConnection>,
+}
+
+impl BlockedInstanceVerifier {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl UrlVerifier for BlockedInstanceVerifier {
+    async fn verify(&self, url: &Url) -> Result<(), activitypub_federation::error::Error> {
+        match blocked_instance::Model::is_suspended(url, &*self.db).await {
+            Ok(false) => Ok(()),
+            Ok(true) => Err(activitypub_federation::error::Error::Other(format!(
+                "instance {} is blocked",
+                url.host_str().unwrap_or_default()
+            ))),
+            Err(err) => Err(activitypub_federation::error::Error::Other(
+                err.inner.to_string(),
+            )),
+        }
+    }
+}
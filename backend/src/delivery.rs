@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use activitypub_federation::{
+    activity_sending::SendActivityTask,
+    config::{Data, FederationConfig},
+};
+use chrono::Utc;
+use futures_util::StreamExt;
+use sea_orm::{ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder, QuerySelect};
+use url::Url;
+
+use crate::{
+    ap::{other_activity::OtherActivity, person::LocalPerson},
+    entity::{delivery, instance},
+    error::{Context, Error},
+    state::State,
+};
+
+/// How long to wait for new deliveries when the queue is empty.
+const IDLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Maximum number of deliveries fetched from the database at once.
+const DELIVERY_BATCH_SIZE: u64 = 50;
+
+/// Maximum number of deliveries sent at the same time.
+const DELIVERY_CONCURRENCY: usize = 8;
+
+/// Sends the queued deliveries until the server is stopped.
+///
+/// Deliveries are stored in the database, so the ones left over from the last run are resumed
+/// on boot.
+pub async fn run(federation_config: FederationConfig<State>) {
+    let stopper = federation_config.to_request_data().stopper.clone();
+    while !stopper.is_stopped() {
+        // Request data counts the fetches made with it, so it is renewed for every batch
+        let data = federation_config.to_request_data();
+        let sent_count = match send_due_deliveries(&data).await {
+            Ok(sent_count) => sent_count,
+            Err(error) => {
+                tracing::warn!("failed to send deliveries\n{:?}", error);
+                0
+            }
+        };
+
+        if sent_count == 0
+            && stopper
+                .stop_future(tokio::time::sleep(IDLE_INTERVAL))
+                .await
+                .is_none()
+        {
+            break;
+        }
+    }
+}
+
+/// Sends the deliveries whose next attempt is due, and returns how many were attempted.
+#[tracing::instrument(skip(data))]
+async fn send_due_deliveries(data: &Data<State>) -> Result<usize, Error> {
+    let deliveries = delivery::Entity::find()
+        .filter(delivery::Column::Failed.eq(false))
+        .filter(delivery::Column::NextAttemptAt.lte(Utc::now().fixed_offset()))
+        .order_by_asc(delivery::Column::NextAttemptAt)
+        .limit(DELIVERY_BATCH_SIZE)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if deliveries.is_empty() {
+        return Ok(0);
+    }
+
+    let me = LocalPerson::get(&*data.db).await?;
+    let dead_hosts = instance::Model::dead_hosts(&*data.db).await?;
+    let count = deliveries.len();
+    futures_util::stream::iter(deliveries)
+        .for_each_concurrent(DELIVERY_CONCURRENCY, |delivery| {
+            let me = &me;
+            let dead_hosts = &dead_hosts;
+            async move {
+                // Dead hosts would only time out, so their deliveries wait until a probe finds
+                // them back instead of using up their attempts
+                let is_dead = Url::parse(&delivery.inbox).is_ok_and(|inbox| {
+                    inbox
+                        .host_str()
+                        .is_some_and(|host| dead_hosts.contains(host))
+                });
+                let result = if is_dead {
+                    delivery.postpone(&*data.db).await
+                } else {
+                    match send(&delivery, me, data).await {
+                        Ok(()) => ModelTrait::delete(delivery, &*data.db)
+                            .await
+                            .context_internal_server_error("failed to delete from database")
+                            .map(|_| ()),
+                        Err(error) => {
+                            tracing::info!("failed to deliver to {}\n{:?}", delivery.inbox, error);
+                            delivery.record_failure(error.to_string(), &*data.db).await
+                        }
+                    }
+                };
+                if let Err(error) = result {
+                    tracing::warn!("failed to update delivery\n{:?}", error);
+                }
+            }
+        })
+        .await;
+
+    Ok(count)
+}
+
+async fn send(
+    delivery: &delivery::Model,
+    me: &LocalPerson,
+    data: &Data<State>,
+) -> Result<(), Error> {
+    let activity: OtherActivity = serde_json::from_value(delivery.data.clone())
+        .context_internal_server_error("malformed stored activity")?;
+    let inbox = Url::parse(&delivery.inbox).context_internal_server_error("malformed inbox URL")?;
+
+    let tasks = SendActivityTask::prepare(&activity, me, vec![inbox.clone()], data).await?;
+    for task in tasks {
+        if let Err(error) = task.sign_and_send(data).await {
+            instance::Model::record_failure(&inbox, &*data.db).await?;
+            return Err(error.into());
+        }
+        instance::Model::record_success(&inbox, &*data.db).await?;
+    }
+
+    Ok(())
+}
@@ -13,7 +13,8 @@ use utoipa::{IntoParams, ToSchema};
 
 use crate::{
     entity::{
-        emoji, follow, hashtag, local_file, mention, post, post_emoji, reaction, remote_file,
+        blocked_instance, delivery, emoji, follow, hashtag, instance, local_file, mention, poll,
+        poll_option, poll_vote, post, post_emoji, post_revision, reaction, relay, remote_file,
         report, sea_orm_active_enums, setting, user,
     },
     error::{Context, Result},
@@ -75,6 +76,10 @@ pub struct User {
     pub banner_url: Option<Url>,
     pub manually_approves_followers: bool,
     pub is_bot: bool,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[schema(value_type = Option<String>, format = "url")]
+    pub moved_to: Option<Url>,
+    pub gone_at: Option<DateTime<FixedOffset>>,
 }
 
 impl User {
@@ -93,6 +98,8 @@ impl User {
             banner_url: user.banner_url.and_then(|url| url.parse().ok()),
             manually_approves_followers: user.manually_approves_followers,
             is_bot: user.is_bot,
+            moved_to: user.moved_to.and_then(|url| url.parse().ok()),
+            gone_at: user.gone_at,
         })
     }
 }
@@ -202,6 +209,88 @@ impl Reaction {
     }
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostRevision {
+    pub created_at: DateTime<FixedOffset>,
+    pub text: String,
+    pub title: Option<String>,
+    pub is_sensitive: bool,
+    pub source_content: Option<String>,
+    pub source_media_type: Option<String>,
+}
+
+impl PostRevision {
+    pub fn from_model(revision: post_revision::Model) -> Self {
+        Self {
+            created_at: revision.created_at,
+            text: revision.text,
+            title: revision.title,
+            is_sensitive: revision.is_sensitive,
+            source_content: revision.source_content,
+            source_media_type: revision.source_media_type,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PollOption {
+    pub name: String,
+    pub votes_count: i32,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Poll {
+    pub end_time: Option<DateTime<FixedOffset>>,
+    pub multiple: bool,
+    pub voters_count: Option<i32>,
+    pub options: Vec<PollOption>,
+    /// Indices of the options the local person voted for.
+    pub own_votes: Vec<u16>,
+}
+
+impl Poll {
+    pub async fn from_model(poll: poll::Model, db: &impl ConnectionTrait) -> Result<Self> {
+        let options = poll
+            .find_related(poll_option::Entity)
+            .order_by_asc(poll_option::Column::Order)
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let options = options
+            .into_iter()
+            .map(|option| PollOption {
+                name: option.name,
+                votes_count: option.votes_count,
+            })
+            .collect::<Vec<_>>();
+
+        let own_votes = poll
+            .find_related(poll_vote::Entity)
+            .filter(poll_vote::Column::UserId.is_null())
+            .select_only()
+            .column(poll_vote::Column::Order)
+            .into_tuple::<i16>()
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let own_votes = own_votes
+            .into_iter()
+            .map(|order| order as u16)
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            end_time: poll.end_time,
+            multiple: poll.multiple,
+            voters_count: poll.voters_count,
+            options,
+            own_votes,
+        })
+    }
+}
+
 #[derive(Derivative, Serialize, ToSchema)]
 #[derivative(Debug)]
 #[serde(rename_all = "camelCase")]
@@ -209,6 +298,8 @@ pub struct Post {
     #[schema(value_type = String, format = "ulid")]
     pub id: Ulid,
     pub created_at: DateTime<FixedOffset>,
+    pub updated_at: Option<DateTime<FixedOffset>>,
+    pub pinned_at: Option<DateTime<FixedOffset>>,
     #[schema(value_type = Option<String>, format = "ulid")]
     pub reply_id: Option<Ulid>,
     #[schema(value_type = Vec<String>, format = "ulid")]
@@ -230,6 +321,8 @@ pub struct Post {
     pub mentions: Vec<Mention>,
     pub emojis: Vec<Emoji>,
     pub hashtags: Vec<String>,
+    pub revisions: Vec<PostRevision>,
+    pub poll: Option<Poll>,
 }
 
 impl Post {
@@ -342,9 +435,33 @@ impl Post {
             .await
             .context_internal_server_error("failed to query database")?;
 
+        let revisions = post
+            .find_related(post_revision::Entity)
+            .order_by_asc(post_revision::Column::CreatedAt)
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let revisions = revisions
+            .into_iter()
+            .map(PostRevision::from_model)
+            .collect::<Vec<_>>();
+
+        let poll = post
+            .find_related(poll::Entity)
+            .one(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let poll = if let Some(poll) = poll {
+            Some(Poll::from_model(poll, db).await?)
+        } else {
+            None
+        };
+
         Ok(Self {
             id: post.id.into(),
             created_at: post.created_at,
+            updated_at: post.updated_at,
+            pinned_at: post.pinned_at,
             reply_id: post.reply_id.map(Into::into),
             replies_id,
             repost_id: post.repost_id.map(Into::into),
@@ -369,6 +486,8 @@ impl Post {
             mentions,
             emojis,
             hashtags,
+            revisions,
+            poll,
         })
     }
 }
@@ -382,21 +501,81 @@ pub struct CreatePost {
     #[schema(value_type = Option<String>, format = "ulid")]
     #[serde(default)]
     pub repost_id: Option<Ulid>,
+    /// Rendered from `source_content` instead if it is given.
+    #[serde(default)]
     pub text: String,
     #[serde(default)]
     pub title: Option<String>,
+    /// MFM or Markdown source of the text.
+    #[serde(default)]
+    pub source_content: Option<String>,
+    /// `text/x.misskeymarkdown` (default) or `text/markdown`.
+    #[serde(default)]
+    pub source_media_type: Option<String>,
     pub visibility: Visibility,
     #[serde(default)]
     pub is_sensitive: bool,
     #[schema(value_type = Vec<String>, format = "ulid")]
     #[serde(default)]
     pub files: Vec<Ulid>,
+    /// Parsed from the text if not given.
     #[serde(default)]
-    pub mentions: Vec<Mention>,
+    pub mentions: Option<Vec<Mention>>,
+    /// Parsed from the text if not given.
     #[serde(default)]
-    pub emojis: Vec<String>,
+    pub emojis: Option<Vec<String>>,
+    /// Parsed from the text if not given.
     #[serde(default)]
-    pub hashtags: Vec<String>,
+    pub hashtags: Option<Vec<String>>,
+    #[serde(default)]
+    pub poll: Option<CreatePoll>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePoll {
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub multiple: bool,
+    #[serde(default)]
+    pub end_time: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateVote {
+    /// Indices of the chosen options.
+    pub choices: Vec<u16>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatePost {
+    /// Rendered from `source_content` instead if it is given.
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// MFM or Markdown source of the text.
+    #[serde(default)]
+    pub source_content: Option<String>,
+    /// `text/x.misskeymarkdown` (default) or `text/markdown`.
+    #[serde(default)]
+    pub source_media_type: Option<String>,
+    #[serde(default)]
+    pub is_sensitive: bool,
+    #[schema(value_type = Vec<String>, format = "ulid")]
+    #[serde(default)]
+    pub files: Vec<Ulid>,
+    /// Parsed from the text if not given.
+    #[serde(default)]
+    pub mentions: Option<Vec<Mention>>,
+    /// Parsed from the text if not given.
+    #[serde(default)]
+    pub emojis: Option<Vec<String>>,
+    /// Parsed from the text if not given.
+    #[serde(default)]
+    pub hashtags: Option<Vec<String>>,
 }
 
 #[derive(Derivative, Serialize, ToSchema)]
@@ -457,6 +636,10 @@ pub struct LocalEmoji {
     #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
     #[schema(value_type = String, format = "url")]
     pub image_url: Url,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[schema(value_type = Option<String>, format = "url")]
+    pub original_uri: Option<Url>,
+    pub license: Option<String>,
 }
 
 impl LocalEmoji {
@@ -472,6 +655,12 @@ impl LocalEmoji {
                 .url
                 .parse()
                 .context_internal_server_error("malformed file URL")?,
+            original_uri: emoji
+                .original_uri
+                .map(|uri| Url::parse(&uri))
+                .transpose()
+                .context_internal_server_error("malformed emoji URI")?,
+            license: emoji.license,
         })
     }
 }
@@ -482,6 +671,52 @@ pub struct CreateEmoji {
     #[schema(value_type = String, format = "ulid")]
     pub file_id: Ulid,
     pub name: String,
+    #[serde(default)]
+    pub license: Option<String>,
+}
+
+#[derive(Derivative, Deserialize, ToSchema)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEmojiFromUri {
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    #[schema(value_type = String, format = "url")]
+    pub uri: Url,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEmojiFromPost {
+    #[schema(value_type = String, format = "ulid")]
+    pub post_id: Ulid,
+    pub emoji_name: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEmojiFromReaction {
+    #[schema(value_type = String, format = "ulid")]
+    pub reaction_id: Ulid,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum ImportEmojiSource {
+    Uri(ImportEmojiFromUri),
+    Post(ImportEmojiFromPost),
+    Reaction(ImportEmojiFromReaction),
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEmoji {
+    /// Local name to register the emoji under.
+    pub name: String,
+    #[serde(flatten)]
+    pub source: ImportEmojiSource,
+    /// Overrides the license of the remote emoji.
+    #[serde(default)]
+    pub license: Option<String>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -523,6 +758,12 @@ pub struct Setting {
     pub maintainer_name: Option<String>,
     pub maintainer_email: Option<String>,
     pub theme_color: Option<String>,
+    pub hide_follows: bool,
+    pub manually_approves_followers: bool,
+    #[schema(value_type = Vec<String>, format = "url")]
+    pub also_known_as: Vec<Url>,
+    #[schema(value_type = Option<String>, format = "url")]
+    pub moved_to: Option<Url>,
 }
 
 impl Setting {
@@ -538,6 +779,10 @@ impl Setting {
             maintainer_name: setting.maintainer_name,
             maintainer_email: setting.maintainer_email,
             theme_color: setting.theme_color,
+            hide_follows: setting.hide_follows,
+            manually_approves_followers: setting.manually_approves_followers,
+            also_known_as: serde_json::from_value(setting.user_also_known_as).unwrap_or_default(),
+            moved_to: setting.user_moved_to.and_then(|url| url.parse().ok()),
         }
     }
 }
@@ -572,3 +817,158 @@ pub struct CreateReport {
     pub user_id: Ulid,
     pub content: String,
 }
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum BlockSeverity {
+    Suspend,
+    RejectMedia,
+    Silence,
+}
+
+impl From<sea_orm_active_enums::BlockSeverity> for BlockSeverity {
+    fn from(value: sea_orm_active_enums::BlockSeverity) -> Self {
+        match value {
+            sea_orm_active_enums::BlockSeverity::Suspend => Self::Suspend,
+            sea_orm_active_enums::BlockSeverity::RejectMedia => Self::RejectMedia,
+            sea_orm_active_enums::BlockSeverity::Silence => Self::Silence,
+        }
+    }
+}
+
+impl From<BlockSeverity> for sea_orm_active_enums::BlockSeverity {
+    fn from(value: BlockSeverity) -> Self {
+        match value {
+            BlockSeverity::Suspend => Self::Suspend,
+            BlockSeverity::RejectMedia => Self::RejectMedia,
+            BlockSeverity::Silence => Self::Silence,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockedInstance {
+    pub host: String,
+    pub severity: BlockSeverity,
+    pub reason: Option<String>,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl BlockedInstance {
+    pub fn from_model(blocked_instance: blocked_instance::Model) -> Self {
+        Self {
+            host: blocked_instance.host,
+            severity: blocked_instance.severity.into(),
+            reason: blocked_instance.reason,
+            created_at: blocked_instance.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBlockedInstance {
+    pub host: String,
+    pub severity: BlockSeverity,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceStatus {
+    pub host: String,
+    pub last_delivered_at: Option<DateTime<FixedOffset>>,
+    pub last_failed_at: Option<DateTime<FixedOffset>>,
+    pub failure_count: i32,
+    pub dead_since: Option<DateTime<FixedOffset>>,
+    pub last_probed_at: Option<DateTime<FixedOffset>>,
+}
+
+impl InstanceStatus {
+    pub fn from_model(instance: instance::Model) -> Self {
+        Self {
+            host: instance.host,
+            last_delivered_at: instance.last_delivered_at,
+            last_failed_at: instance.last_failed_at,
+            failure_count: instance.failure_count,
+            dead_since: instance.dead_since,
+            last_probed_at: instance.last_probed_at,
+        }
+    }
+}
+
+#[derive(Derivative, Serialize, ToSchema)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Relay {
+    #[schema(value_type = String, format = "ulid")]
+    pub id: Ulid,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    #[schema(value_type = String, format = "url")]
+    pub inbox: Url,
+    #[derivative(Debug(format_with = "crate::fmt::debug_format_option_display"))]
+    #[schema(value_type = Option<String>, format = "url")]
+    pub actor: Option<Url>,
+    pub accepted: bool,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl Relay {
+    pub fn from_model(relay: relay::Model) -> Result<Self> {
+        Ok(Self {
+            id: relay.id.into(),
+            inbox: Url::parse(&relay.inbox)
+                .context_internal_server_error("malformed relay inbox URL")?,
+            actor: relay
+                .actor
+                .as_deref()
+                .map(Url::parse)
+                .transpose()
+                .context_internal_server_error("malformed relay actor URI")?,
+            accepted: relay.accepted,
+            created_at: relay.created_at,
+        })
+    }
+}
+
+#[derive(Derivative, Deserialize, ToSchema)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateRelay {
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    #[schema(value_type = String, format = "url")]
+    pub inbox: Url,
+}
+
+#[derive(Derivative, Serialize, ToSchema)]
+#[derivative(Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Delivery {
+    #[schema(value_type = String, format = "ulid")]
+    pub id: Ulid,
+    #[derivative(Debug(format_with = "std::fmt::Display::fmt"))]
+    #[schema(value_type = String, format = "url")]
+    pub inbox: Url,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub failed: bool,
+    pub next_attempt_at: DateTime<FixedOffset>,
+    pub created_at: DateTime<FixedOffset>,
+}
+
+impl Delivery {
+    pub fn from_model(delivery: delivery::Model) -> Result<Self> {
+        Ok(Self {
+            id: delivery.id.into(),
+            inbox: Url::parse(&delivery.inbox)
+                .context_internal_server_error("malformed delivery inbox URL")?,
+            attempts: delivery.attempts,
+            last_error: delivery.last_error,
+            failed: delivery.failed,
+            next_attempt_at: delivery.next_attempt_at,
+            created_at: delivery.created_at,
+        })
+    }
+}
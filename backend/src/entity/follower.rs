@@ -9,6 +9,7 @@ pub struct Model {
     pub from_id: Uuid,
     #[sea_orm(unique)]
     pub uri: String,
+    pub accepted: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -3,16 +3,25 @@
 pub mod prelude;
 
 pub mod access_key;
+pub mod activity;
+pub mod blocked_instance;
+pub mod delivery;
 pub mod emoji;
 pub mod follow;
 pub mod follower;
 pub mod hashtag;
+pub mod instance;
 pub mod local_file;
 pub mod mention;
 pub mod notification;
+pub mod poll;
+pub mod poll_option;
+pub mod poll_vote;
 pub mod post;
 pub mod post_emoji;
+pub mod post_revision;
 pub mod reaction;
+pub mod relay;
 pub mod remote_file;
 pub mod report;
 pub mod sea_orm_active_enums;
@@ -20,16 +20,22 @@ pub struct Model {
     pub repost_id: Option<Uuid>,
     pub source_content: Option<String>,
     pub source_media_type: Option<String>,
+    pub updated_at: Option<DateTimeWithTimeZone>,
+    pub pinned_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::activity::Entity")]
+    Activity,
     #[sea_orm(has_many = "super::hashtag::Entity")]
     Hashtag,
     #[sea_orm(has_many = "super::local_file::Entity")]
     LocalFile,
     #[sea_orm(has_many = "super::mention::Entity")]
     Mention,
+    #[sea_orm(has_one = "super::poll::Entity")]
+    Poll,
     #[sea_orm(
         belongs_to = "Entity",
         from = "Column::ReplyId",
@@ -48,6 +54,8 @@ pub enum Relation {
     SelfRef1,
     #[sea_orm(has_many = "super::post_emoji::Entity")]
     PostEmoji,
+    #[sea_orm(has_many = "super::post_revision::Entity")]
+    PostRevision,
     #[sea_orm(has_many = "super::reaction::Entity")]
     Reaction,
     #[sea_orm(has_many = "super::remote_file::Entity")]
@@ -62,6 +70,12 @@ pub enum Relation {
     User,
 }
 
+impl Related<super::activity::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Activity.def()
+    }
+}
+
 impl Related<super::hashtag::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Hashtag.def()
@@ -80,12 +94,24 @@ impl Related<super::mention::Entity> for Entity {
     }
 }
 
+impl Related<super::poll::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Poll.def()
+    }
+}
+
 impl Related<super::post_emoji::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::PostEmoji.def()
     }
 }
 
+impl Related<super::post_revision::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PostRevision.def()
+    }
+}
+
 impl Related<super::reaction::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Reaction.def()
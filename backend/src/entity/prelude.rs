@@ -2,16 +2,25 @@
 #![allow(unused_imports)]
 
 pub use super::access_key::Entity as AccessKey;
+pub use super::activity::Entity as Activity;
+pub use super::blocked_instance::Entity as BlockedInstance;
+pub use super::delivery::Entity as Delivery;
 pub use super::emoji::Entity as Emoji;
 pub use super::follow::Entity as Follow;
 pub use super::follower::Entity as Follower;
 pub use super::hashtag::Entity as Hashtag;
+pub use super::instance::Entity as Instance;
 pub use super::local_file::Entity as LocalFile;
 pub use super::mention::Entity as Mention;
 pub use super::notification::Entity as Notification;
+pub use super::poll::Entity as Poll;
+pub use super::poll_option::Entity as PollOption;
+pub use super::poll_vote::Entity as PollVote;
 pub use super::post::Entity as Post;
 pub use super::post_emoji::Entity as PostEmoji;
+pub use super::post_revision::Entity as PostRevision;
 pub use super::reaction::Entity as Reaction;
+pub use super::relay::Entity as Relay;
 pub use super::remote_file::Entity as RemoteFile;
 pub use super::report::Entity as Report;
 pub use super::setting::Entity as Setting;
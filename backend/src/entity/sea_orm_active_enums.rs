@@ -2,6 +2,16 @@
 
 use sea_orm::entity::prelude::*;
 
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "block_severity")]
+pub enum BlockSeverity {
+    #[sea_orm(string_value = "reject_media")]
+    RejectMedia,
+    #[sea_orm(string_value = "silence")]
+    Silence,
+    #[sea_orm(string_value = "suspend")]
+    Suspend,
+}
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "object_store_type")]
 pub enum ObjectStoreType {
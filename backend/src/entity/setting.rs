@@ -20,6 +20,12 @@ pub struct Model {
     pub maintainer_email: Option<String>,
     pub theme_color: Option<String>,
     pub user_description: Option<String>,
+    pub hide_follows: bool,
+    pub manually_approves_followers: bool,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub user_also_known_as: Json,
+    pub user_moved_to: Option<String>,
+    pub remote_html_sanitize_pending: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
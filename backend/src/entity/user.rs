@@ -21,6 +21,10 @@ pub struct Model {
     pub manually_approves_followers: bool,
     pub is_bot: bool,
     pub description: Option<String>,
+    #[sea_orm(column_type = "JsonBinary")]
+    pub also_known_as: Json,
+    pub moved_to: Option<String>,
+    pub gone_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -29,6 +33,8 @@ pub enum Relation {
     Follow,
     #[sea_orm(has_many = "super::follower::Entity")]
     Follower,
+    #[sea_orm(has_many = "super::poll_vote::Entity")]
+    PollVote,
     #[sea_orm(has_many = "super::post::Entity")]
     Post,
     #[sea_orm(has_many = "super::reaction::Entity")]
@@ -49,6 +55,12 @@ impl Related<super::follower::Entity> for Entity {
     }
 }
 
+impl Related<super::poll_vote::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PollVote.def()
+    }
+}
+
 impl Related<super::post::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Post.def()
@@ -1,9 +1,15 @@
+mod activity;
+mod blocked_instance;
+mod delivery;
 mod emoji;
 mod enums;
 mod follow;
 mod follower;
+mod instance;
 mod local_file;
+mod poll;
 mod post;
 mod reaction;
+mod relay;
 mod setting;
 mod user;
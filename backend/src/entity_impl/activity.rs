@@ -0,0 +1,100 @@
+use activitypub_federation::kinds::public;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QuerySelect,
+};
+use serde::Serialize;
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    config::CONFIG,
+    entity::{activity, post, user},
+    error::{Context, Error},
+};
+
+fn is_public_uri(uri: &str) -> bool {
+    uri == public().as_str() || uri == "as:Public" || uri == "Public"
+}
+
+impl activity::Model {
+    pub fn parse_ap_id(url: &str) -> Option<Ulid> {
+        url.strip_prefix(&format!("https://{}/object/", CONFIG.public_domain))
+            .and_then(|id| id.parse().ok())
+    }
+
+    /// Records an outgoing activity so that it can be fetched by its ID.
+    ///
+    /// Only the activities with IDs from `generate_object_id` are recorded, as the others are
+    /// served by their own objects. The record is removed along with the post at `post_uri`.
+    /// `inboxes` are kept so that the recipients can fetch non-public activities.
+    pub async fn record(
+        activity: &impl Serialize,
+        id: &Url,
+        post_uri: Option<&Url>,
+        inboxes: &[Url],
+        db: &impl ConnectionTrait,
+    ) -> Result<(), Error> {
+        if let Some(id) = Self::parse_ap_id(id.as_str()) {
+            let post_id = if let Some(post_uri) = post_uri {
+                post::Entity::find()
+                    .filter(post::Column::Uri.eq(post_uri.as_str()))
+                    .select_only()
+                    .column(post::Column::Id)
+                    .into_tuple::<uuid::Uuid>()
+                    .one(db)
+                    .await
+                    .context_internal_server_error("failed to query database")?
+            } else {
+                None
+            };
+            let data = serde_json::to_value(activity)
+                .context_internal_server_error("failed to serialize activity")?;
+            let recipients = serde_json::to_value(inboxes)
+                .context_internal_server_error("failed to serialize recipients")?;
+
+            let activity_activemodel = activity::ActiveModel {
+                id: ActiveValue::Set(id.into()),
+                post_id: ActiveValue::Set(post_id),
+                data: ActiveValue::Set(data),
+                created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+                recipients: ActiveValue::Set(recipients),
+            };
+            activity_activemodel
+                .insert(db)
+                .await
+                .context_internal_server_error("failed to insert to database")?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether the activity is addressed to the public.
+    pub fn is_public(&self) -> bool {
+        ["to", "cc"]
+            .into_iter()
+            .filter_map(|key| self.data.get(key))
+            .any(|addressing| match addressing {
+                serde_json::Value::String(uri) => is_public_uri(uri),
+                serde_json::Value::Array(uris) => uris
+                    .iter()
+                    .filter_map(serde_json::Value::as_str)
+                    .any(is_public_uri),
+                _ => false,
+            })
+    }
+
+    /// Returns whether the activity was delivered to the inbox of the user.
+    pub fn is_delivered_to(&self, user: &user::Model) -> bool {
+        let recipients =
+            serde_json::from_value::<Vec<String>>(self.recipients.clone()).unwrap_or_default();
+        recipients.iter().any(|inbox| {
+            *inbox == user.inbox
+                || user
+                    .shared_inbox
+                    .as_ref()
+                    .is_some_and(|shared| inbox == shared)
+        })
+    }
+}
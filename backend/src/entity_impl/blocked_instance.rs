@@ -0,0 +1,86 @@
+use std::collections::HashSet;
+
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect,
+};
+use url::Url;
+
+use crate::{
+    entity::{blocked_instance, sea_orm_active_enums::BlockSeverity},
+    error::{Context, Error},
+    format_err,
+};
+
+impl blocked_instance::Model {
+    /// Returns the host and all of its parent domains, so that blocking a domain also blocks its
+    /// subdomains.
+    fn candidate_hosts(host: &str) -> Vec<String> {
+        let labels = host.split('.').collect::<Vec<_>>();
+        (0..labels.len())
+            .map(|idx| labels[idx..].join("."))
+            .collect::<Vec<_>>()
+    }
+
+    async fn has_severity(
+        url: &Url,
+        severities: impl IntoIterator<Item = BlockSeverity>,
+        db: &impl ConnectionTrait,
+    ) -> Result<bool, Error> {
+        let candidate_hosts = url
+            .host_str()
+            .map(Self::candidate_hosts)
+            .unwrap_or_default();
+        let count = blocked_instance::Entity::find()
+            .filter(blocked_instance::Column::Host.is_in(candidate_hosts))
+            .filter(blocked_instance::Column::Severity.is_in(severities))
+            .count(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        Ok(count > 0)
+    }
+
+    /// Returns the hosts of all suspended instances.
+    pub async fn suspended_hosts(db: &impl ConnectionTrait) -> Result<HashSet<String>, Error> {
+        let hosts = blocked_instance::Entity::find()
+            .filter(blocked_instance::Column::Severity.eq(BlockSeverity::Suspend))
+            .select_only()
+            .column(blocked_instance::Column::Host)
+            .into_tuple::<String>()
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        Ok(hosts.into_iter().collect())
+    }
+
+    /// Returns whether `host` or one of its parent domains is in `blocked_hosts`.
+    pub fn is_host_in(host: &str, blocked_hosts: &HashSet<String>) -> bool {
+        Self::candidate_hosts(host)
+            .iter()
+            .any(|host| blocked_hosts.contains(host))
+    }
+
+    pub async fn is_suspended(url: &Url, db: &impl ConnectionTrait) -> Result<bool, Error> {
+        Self::has_severity(url, [BlockSeverity::Suspend], db).await
+    }
+
+    pub async fn is_media_rejected(url: &Url, db: &impl ConnectionTrait) -> Result<bool, Error> {
+        Self::has_severity(
+            url,
+            [BlockSeverity::Suspend, BlockSeverity::RejectMedia],
+            db,
+        )
+        .await
+    }
+
+    pub async fn is_silenced(url: &Url, db: &impl ConnectionTrait) -> Result<bool, Error> {
+        Self::has_severity(url, [BlockSeverity::Suspend, BlockSeverity::Silence], db).await
+    }
+
+    pub async fn verify_not_suspended(url: &Url, db: &impl ConnectionTrait) -> Result<(), Error> {
+        if Self::is_suspended(url, db).await? {
+            Err(format_err!(FORBIDDEN, "instance is blocked"))
+        } else {
+            Ok(())
+        }
+    }
+}
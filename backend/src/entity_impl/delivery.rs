@@ -0,0 +1,288 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait,
+    QueryFilter, QuerySelect,
+};
+use serde::Serialize;
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    entity::delivery,
+    error::{Context, Error},
+};
+
+/// Deliveries are given up after this many failed attempts.
+const MAX_ATTEMPTS: i32 = 10;
+
+/// Delay before the first retry, doubled on every following failure.
+const RETRY_BASE_DELAY_SECONDS: i64 = 60;
+
+/// Deliveries to dead hosts are postponed by this long, unless the host is back earlier.
+const DEAD_HOST_DELAY_HOURS: i64 = 24;
+
+/// Returns the delay before the next attempt after `attempts` failed ones.
+fn retry_delay(attempts: i32) -> ChronoDuration {
+    let doublings = attempts.saturating_sub(1).clamp(0, MAX_ATTEMPTS);
+    ChronoDuration::seconds(RETRY_BASE_DELAY_SECONDS << doublings)
+}
+
+/// Returns whether the delivery is given up after `attempts` failed ones.
+fn is_given_up(attempts: i32) -> bool {
+    attempts >= MAX_ATTEMPTS
+}
+
+impl delivery::Model {
+    /// Queues the activity to be delivered to each of the inboxes.
+    pub async fn enqueue(
+        activity: &impl Serialize,
+        inboxes: Vec<Url>,
+        db: &impl ConnectionTrait,
+    ) -> Result<(), Error> {
+        let mut inboxes = inboxes;
+        inboxes.sort();
+        inboxes.dedup();
+        if inboxes.is_empty() {
+            return Ok(());
+        }
+
+        let data = serde_json::to_value(activity)
+            .context_internal_server_error("failed to serialize activity")?;
+        let now = Utc::now().fixed_offset();
+        let deliveries = inboxes.into_iter().map(|inbox| delivery::ActiveModel {
+            id: ActiveValue::Set(Ulid::new().into()),
+            inbox: ActiveValue::Set(inbox.to_string()),
+            data: ActiveValue::Set(data.clone()),
+            attempts: ActiveValue::Set(0),
+            next_attempt_at: ActiveValue::Set(now),
+            last_error: ActiveValue::Set(None),
+            failed: ActiveValue::Set(false),
+            created_at: ActiveValue::Set(now),
+        });
+        delivery::Entity::insert_many(deliveries)
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+
+        Ok(())
+    }
+
+    /// Records a failed attempt and schedules the next one with exponential backoff.
+    pub async fn record_failure(
+        &self,
+        error: String,
+        db: &impl ConnectionTrait,
+    ) -> Result<(), Error> {
+        let attempts = self.attempts + 1;
+        let delivery_activemodel = delivery::ActiveModel {
+            id: ActiveValue::Unchanged(self.id),
+            attempts: ActiveValue::Set(attempts),
+            next_attempt_at: ActiveValue::Set((Utc::now() + retry_delay(attempts)).fixed_offset()),
+            last_error: ActiveValue::Set(Some(error)),
+            failed: ActiveValue::Set(is_given_up(attempts)),
+            ..Default::default()
+        };
+        delivery_activemodel
+            .update(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+        Ok(())
+    }
+
+    /// Postpones the delivery while its host is dead, without counting it as an attempt.
+    pub async fn postpone(&self, db: &impl ConnectionTrait) -> Result<(), Error> {
+        let delivery_activemodel = delivery::ActiveModel {
+            id: ActiveValue::Unchanged(self.id),
+            next_attempt_at: ActiveValue::Set(
+                (Utc::now() + ChronoDuration::hours(DEAD_HOST_DELAY_HOURS)).fixed_offset(),
+            ),
+            ..Default::default()
+        };
+        delivery_activemodel
+            .update(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+        Ok(())
+    }
+
+    /// Schedules the pending deliveries to the host to be attempted right away, once it is back.
+    pub async fn resume_host(host: &str, db: &impl ConnectionTrait) -> Result<u64, Error> {
+        let deliveries = delivery::Entity::find()
+            .filter(delivery::Column::Failed.eq(false))
+            .select_only()
+            .column(delivery::Column::Id)
+            .column(delivery::Column::Inbox)
+            .into_tuple::<(uuid::Uuid, String)>()
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let ids = deliveries
+            .into_iter()
+            .filter(|(_, inbox)| {
+                Url::parse(inbox).is_ok_and(|inbox| inbox.host_str() == Some(host))
+            })
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let result = delivery::Entity::update_many()
+            .col_expr(
+                delivery::Column::NextAttemptAt,
+                Expr::value(Utc::now().fixed_offset()),
+            )
+            .filter(delivery::Column::Id.is_in(ids))
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+        Ok(result.rows_affected)
+    }
+
+    /// Schedules the failed deliveries, or only the one with `id`, to be attempted again right
+    /// away.
+    pub async fn retry_failed(id: Option<Ulid>, db: &impl ConnectionTrait) -> Result<u64, Error> {
+        let update = delivery::Entity::update_many()
+            .col_expr(delivery::Column::Attempts, Expr::value(0))
+            .col_expr(
+                delivery::Column::NextAttemptAt,
+                Expr::value(Utc::now().fixed_offset()),
+            )
+            .col_expr(delivery::Column::Failed, Expr::value(false))
+            .filter(delivery::Column::Failed.eq(true));
+        let update = if let Some(id) = id {
+            update.filter(delivery::Column::Id.eq(uuid::Uuid::from(id)))
+        } else {
+            update
+        };
+        let result = update
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+        Ok(result.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, MockExecResult, Value};
+
+    use super::*;
+
+    fn delivery(inbox: &str) -> delivery::Model {
+        let now = Utc::now().fixed_offset();
+        delivery::Model {
+            id: uuid::Uuid::new_v4(),
+            inbox: inbox.to_string(),
+            data: serde_json::json!({}),
+            attempts: 3,
+            next_attempt_at: now,
+            last_error: None,
+            failed: false,
+            created_at: now,
+        }
+    }
+
+    /// Returns the statements run on the database.
+    fn statements(db: DatabaseConnection) -> Vec<String> {
+        db.into_transaction_log()
+            .into_iter()
+            .map(|transaction| format!("{:?}", transaction))
+            .collect()
+    }
+
+    #[test]
+    fn retry_delay_doubles_per_attempt() {
+        assert_eq!(
+            retry_delay(0),
+            ChronoDuration::seconds(RETRY_BASE_DELAY_SECONDS)
+        );
+        assert_eq!(
+            retry_delay(1),
+            ChronoDuration::seconds(RETRY_BASE_DELAY_SECONDS)
+        );
+        assert_eq!(
+            retry_delay(2),
+            ChronoDuration::seconds(RETRY_BASE_DELAY_SECONDS * 2)
+        );
+        assert_eq!(
+            retry_delay(4),
+            ChronoDuration::seconds(RETRY_BASE_DELAY_SECONDS * 8)
+        );
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(retry_delay(MAX_ATTEMPTS + 1), retry_delay(i32::MAX));
+    }
+
+    #[test]
+    fn delivery_is_given_up_after_max_attempts() {
+        assert!(!is_given_up(MAX_ATTEMPTS - 1));
+        assert!(is_given_up(MAX_ATTEMPTS));
+    }
+
+    #[tokio::test]
+    async fn last_failure_gives_up() {
+        let delivery = delivery::Model {
+            attempts: MAX_ATTEMPTS - 1,
+            ..delivery("https://remote.example/inbox")
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[delivery.clone()]])
+            .into_connection();
+        delivery
+            .record_failure("timed out".to_string(), &db)
+            .await
+            .unwrap();
+
+        let statements = statements(db);
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].contains(&format!("Int(Some({}))", MAX_ATTEMPTS)));
+        assert!(statements[0].contains("Bool(Some(true))"));
+    }
+
+    #[tokio::test]
+    async fn postpone_does_not_count_attempt() {
+        let delivery = delivery("https://dead.example/inbox");
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[delivery.clone()]])
+            .into_connection();
+        delivery.postpone(&db).await.unwrap();
+
+        let statements = statements(db);
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].contains(r#"\"next_attempt_at\" = $"#));
+        assert!(!statements[0].contains(r#"\"attempts\" = $"#));
+        assert!(!statements[0].contains(r#"\"failed\" = $"#));
+    }
+
+    #[tokio::test]
+    async fn resume_host_only_resumes_its_deliveries() {
+        let back = delivery("https://back.example/inbox");
+        let other = delivery("https://other.example/inbox");
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[&back, &other].map(|delivery| {
+                BTreeMap::from([
+                    ("id", Value::from(delivery.id)),
+                    ("inbox", Value::from(delivery.inbox.clone())),
+                ])
+            })])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let resumed = delivery::Model::resume_host("back.example", &db)
+            .await
+            .unwrap();
+        assert_eq!(resumed, 1);
+
+        let statements = statements(db);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[1].contains(&back.id.to_string()));
+        assert!(!statements[1].contains(&other.id.to_string()));
+    }
+}
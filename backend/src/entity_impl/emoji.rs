@@ -1,19 +1,32 @@
 use activitypub_federation::{
-    config::Data, protocol::verification::verify_domains_match, traits::Object,
+    config::{Data, UrlVerifier},
+    protocol::verification::verify_domains_match,
+    traits::Object,
 };
 use async_trait::async_trait;
-use sea_orm::{EntityTrait, ModelTrait};
+use axum::body::Bytes;
+use chrono::Utc;
+use mime::Mime;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, EntityTrait, ModelTrait, PaginatorTrait, TransactionTrait,
+};
 use url::Url;
 
 use crate::{
-    ap::tag::{Emoji, EmojiIcon},
+    ap::{
+        tag::{Emoji, EmojiIcon, EmojiLicense},
+        url_verifier::BlockedInstanceVerifier,
+    },
     config::CONFIG,
-    entity::{emoji, local_file},
+    entity::{blocked_instance, emoji, local_file},
     error::{Context, Error},
     format_err,
     state::State,
 };
 
+/// Maximum size of an imported emoji image in bytes.
+const MAX_EMOJI_IMAGE_SIZE: usize = 1024 * 1024;
+
 impl emoji::Model {
     pub fn ap_id(&self) -> Result<Url, Error> {
         Url::parse(&format!(
@@ -27,6 +40,95 @@ impl emoji::Model {
         url.strip_prefix(&format!("https://{}/emoji/", CONFIG.public_domain))
             .map(str::to_string)
     }
+
+    /// Downloads the image of a remote emoji into the object store and registers it under
+    /// `name`.
+    #[tracing::instrument(skip(data))]
+    pub async fn import(
+        name: String,
+        image_url: Url,
+        media_type: Mime,
+        original_uri: Url,
+        license: Option<String>,
+        data: &Data<State>,
+    ) -> Result<Self, Error> {
+        if Self::parse_ap_id(original_uri.as_str()).is_some() {
+            return Err(format_err!(BAD_REQUEST, "emoji is already local"));
+        }
+        if media_type.type_() != mime::IMAGE {
+            return Err(format_err!(BAD_REQUEST, "emoji is not an image"));
+        }
+        if blocked_instance::Model::is_media_rejected(&image_url, &*data.db).await? {
+            return Err(format_err!(FORBIDDEN, "instance is blocked"));
+        }
+
+        let existing_count = emoji::Entity::find_by_id(&name)
+            .count(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        if existing_count > 0 {
+            return Err(format_err!(CONFLICT, "emoji name already exists"));
+        }
+
+        let verifier = BlockedInstanceVerifier::new(data.db.clone());
+        if let Err(error) = verifier.verify(&image_url).await {
+            return Err(format_err!(FORBIDDEN, "{}", error));
+        }
+
+        let mut resp = data
+            .http_client
+            .get(image_url)
+            .send()
+            .await
+            .context_internal_server_error("failed to request HTTP")?
+            .error_for_status()
+            .context_internal_server_error("target server returned error")?;
+        if resp
+            .content_length()
+            .is_some_and(|len| len > MAX_EMOJI_IMAGE_SIZE as u64)
+        {
+            return Err(format_err!(BAD_REQUEST, "emoji image is too large"));
+        }
+        let mut image = Vec::new();
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .context_internal_server_error("failed to read emoji image")?
+        {
+            if image.len() + chunk.len() > MAX_EMOJI_IMAGE_SIZE {
+                return Err(format_err!(BAD_REQUEST, "emoji image is too large"));
+            }
+            image.extend_from_slice(&chunk);
+        }
+        let image = Bytes::from(image);
+
+        let tx = data
+            .db
+            .begin()
+            .await
+            .context_internal_server_error("failed to begin database transaction")?;
+
+        let file = local_file::Model::put(image, media_type, None, &tx).await?;
+
+        let emoji_activemodel = emoji::ActiveModel {
+            name: ActiveValue::Set(name),
+            created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+            original_uri: ActiveValue::Set(Some(original_uri.to_string())),
+            license: ActiveValue::Set(license),
+        };
+        let emoji = emoji_activemodel
+            .insert(&tx)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+
+        file.attach_to_emoji(emoji.name.clone(), &tx).await?;
+
+        tx.commit()
+            .await
+            .context_internal_server_error("failed to commit database transaction")?;
+
+        Ok(emoji)
+    }
 }
 
 #[async_trait]
@@ -74,6 +176,9 @@ impl Object for emoji::Model {
                     .parse()
                     .context_internal_server_error("malformed file URL")?,
             },
+            license: self.license.map(|license| EmojiLicense {
+                free_text: Some(license),
+            }),
         })
     }
 
@@ -6,14 +6,14 @@ use activitypub_federation::{
 };
 use async_trait::async_trait;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter,
-    QuerySelect, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QuerySelect,
+    TransactionTrait,
 };
 use url::Url;
 
 use crate::{
     ap::{follow::Follow, person::LocalPerson},
-    entity::{follower, user},
+    entity::{follower, setting, user},
     error::{Context, Error},
     queue::{Event, Notification, NotificationType},
     state::State,
@@ -75,9 +75,11 @@ impl Object for follower::Model {
         let uri = json.id().clone();
         let actor: ObjectId<user::Model> = json.actor.into();
         let from_user = actor.dereference(data).await?;
+        let setting = setting::Model::get(&*data.db).await?;
         let this = Self {
             from_id: from_user.id,
             uri: uri.to_string(),
+            accepted: !setting.manually_approves_followers,
         };
 
         let tx = data
@@ -86,17 +88,19 @@ impl Object for follower::Model {
             .await
             .context_internal_server_error("failed to begin database transaction")?;
 
-        let existing_count = follower::Entity::find()
+        let existing = follower::Entity::find()
             .filter(
                 follower::Column::Uri
                     .eq(uri.as_str())
                     .or(follower::Column::FromId.eq(from_user.id)),
             )
-            .count(&tx)
+            .one(&tx)
             .await
             .context_internal_server_error("failed to query database")?;
 
-        let this = if existing_count == 0 {
+        let this = if let Some(existing) = existing {
+            existing
+        } else {
             let this_activemodel: follower::ActiveModel = this.into();
             let this = this_activemodel
                 .insert(&tx)
@@ -106,8 +110,6 @@ impl Object for follower::Model {
                 .await
                 .context_internal_server_error("failed to commit database transaction")?;
             this
-        } else {
-            this
         };
 
         Ok(this)
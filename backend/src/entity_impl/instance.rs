@@ -0,0 +1,350 @@
+use std::collections::HashSet;
+
+use activitypub_federation::config::Data;
+use chrono::{Duration as ChronoDuration, Utc};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveValue, ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect,
+};
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    entity::instance,
+    error::{Context, Error},
+    state::State,
+};
+
+/// Hosts are considered dead after this many consecutive delivery failures, provided that
+/// nothing has been delivered to them for `DEAD_AFTER_DAYS`.
+const DEAD_FAILURE_COUNT: i32 = 10;
+
+/// Minimum time since the last successful delivery before a host is considered dead.
+const DEAD_AFTER_DAYS: i64 = 3;
+
+#[derive(Deserialize)]
+struct NodeInfoWellKnownLinks {
+    href: Url,
+}
+
+#[derive(Deserialize)]
+struct NodeInfoWellKnown {
+    links: Vec<NodeInfoWellKnownLinks>,
+}
+
+#[derive(Deserialize)]
+struct NodeInfoSoftware {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct NodeInfo {
+    software: NodeInfoSoftware,
+}
+
+impl instance::Model {
+    /// Records a successful delivery to the host of the inbox, reviving it if it was dead.
+    pub async fn record_success(inbox: &Url, db: &impl ConnectionTrait) -> Result<(), Error> {
+        let host = inbox
+            .host_str()
+            .context_internal_server_error("inbox URL has no host")?;
+        let now = Utc::now().fixed_offset();
+        let instance_activemodel = instance::ActiveModel {
+            host: ActiveValue::Set(host.to_string()),
+            last_delivered_at: ActiveValue::Set(Some(now)),
+            last_failed_at: ActiveValue::NotSet,
+            failure_count: ActiveValue::Set(0),
+            dead_since: ActiveValue::Set(None),
+            last_probed_at: ActiveValue::NotSet,
+            software: ActiveValue::NotSet,
+            created_at: ActiveValue::Set(now),
+        };
+        instance::Entity::insert(instance_activemodel)
+            .on_conflict(
+                OnConflict::column(instance::Column::Host)
+                    .update_columns([
+                        instance::Column::LastDeliveredAt,
+                        instance::Column::FailureCount,
+                        instance::Column::DeadSince,
+                    ])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+        Ok(())
+    }
+
+    /// Records a failed delivery to the host of the inbox, and marks it dead if it has been
+    /// failing for too long.
+    pub async fn record_failure(inbox: &Url, db: &impl ConnectionTrait) -> Result<(), Error> {
+        let host = inbox
+            .host_str()
+            .context_internal_server_error("inbox URL has no host")?;
+        let now = Utc::now().fixed_offset();
+        let instance_activemodel = instance::ActiveModel {
+            host: ActiveValue::Set(host.to_string()),
+            last_delivered_at: ActiveValue::NotSet,
+            last_failed_at: ActiveValue::Set(Some(now)),
+            failure_count: ActiveValue::Set(1),
+            dead_since: ActiveValue::NotSet,
+            last_probed_at: ActiveValue::NotSet,
+            software: ActiveValue::NotSet,
+            created_at: ActiveValue::Set(now),
+        };
+        instance::Entity::insert(instance_activemodel)
+            .on_conflict(
+                OnConflict::column(instance::Column::Host)
+                    .value(
+                        instance::Column::FailureCount,
+                        Expr::col((instance::Entity, instance::Column::FailureCount)).add(1),
+                    )
+                    .update_column(instance::Column::LastFailedAt)
+                    .to_owned(),
+            )
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+
+        let alive_until = (Utc::now() - ChronoDuration::days(DEAD_AFTER_DAYS)).fixed_offset();
+        instance::Entity::update_many()
+            .col_expr(instance::Column::DeadSince, Expr::value(now))
+            .filter(instance::Column::Host.eq(host))
+            .filter(instance::Column::DeadSince.is_null())
+            .filter(instance::Column::FailureCount.gte(DEAD_FAILURE_COUNT))
+            .filter(
+                Condition::any()
+                    .add(instance::Column::LastDeliveredAt.lt(alive_until))
+                    .add(
+                        Condition::all()
+                            .add(instance::Column::LastDeliveredAt.is_null())
+                            .add(instance::Column::CreatedAt.lt(alive_until)),
+                    ),
+            )
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+
+        Ok(())
+    }
+
+    /// Records the result of probing a dead host, reviving it if it responded.
+    pub async fn record_probe(
+        &self,
+        is_alive: bool,
+        db: &impl ConnectionTrait,
+    ) -> Result<(), Error> {
+        let now = Utc::now().fixed_offset();
+        let instance_activemodel = if is_alive {
+            instance::ActiveModel {
+                host: ActiveValue::Unchanged(self.host.clone()),
+                failure_count: ActiveValue::Set(0),
+                dead_since: ActiveValue::Set(None),
+                last_probed_at: ActiveValue::Set(Some(now)),
+                ..Default::default()
+            }
+        } else {
+            instance::ActiveModel {
+                host: ActiveValue::Unchanged(self.host.clone()),
+                last_probed_at: ActiveValue::Set(Some(now)),
+                ..Default::default()
+            }
+        };
+        instance::Entity::update(instance_activemodel)
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+        Ok(())
+    }
+
+    /// Returns the hosts that are currently considered dead.
+    pub async fn dead_hosts(db: &impl ConnectionTrait) -> Result<HashSet<String>, Error> {
+        let hosts = instance::Entity::find()
+            .filter(instance::Column::DeadSince.is_not_null())
+            .select_only()
+            .column(instance::Column::Host)
+            .into_tuple::<String>()
+            .all(db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        Ok(hosts.into_iter().collect())
+    }
+
+    /// Returns the software name the host of the URL reports in its nodeinfo, fetching and
+    /// caching it if it is not known yet. Returns `None` if the nodeinfo could not be fetched.
+    pub async fn software(url: &Url, data: &Data<State>) -> Result<Option<String>, Error> {
+        let host = url
+            .host_str()
+            .context_internal_server_error("URL has no host")?;
+        let instance = instance::Entity::find_by_id(host)
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        if let Some(software) = instance.and_then(|instance| instance.software) {
+            return Ok(Some(software));
+        }
+
+        let software = match Self::fetch_software(host, data).await {
+            Ok(software) => software,
+            Err(error) => {
+                tracing::warn!("failed to fetch nodeinfo of {host}: {error}");
+                return Ok(None);
+            }
+        };
+
+        let instance_activemodel = instance::ActiveModel {
+            host: ActiveValue::Set(host.to_string()),
+            last_delivered_at: ActiveValue::NotSet,
+            last_failed_at: ActiveValue::NotSet,
+            failure_count: ActiveValue::Set(0),
+            dead_since: ActiveValue::NotSet,
+            last_probed_at: ActiveValue::NotSet,
+            software: ActiveValue::Set(Some(software.clone())),
+            created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+        };
+        instance::Entity::insert(instance_activemodel)
+            .on_conflict(
+                OnConflict::column(instance::Column::Host)
+                    .update_column(instance::Column::Software)
+                    .to_owned(),
+            )
+            .exec(&*data.db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+
+        Ok(Some(software))
+    }
+
+    async fn fetch_software(host: &str, data: &Data<State>) -> Result<String, Error> {
+        let well_known = data
+            .http_client
+            .get(format!("https://{host}/.well-known/nodeinfo"))
+            .send()
+            .await
+            .context_internal_server_error("failed to request HTTP")?
+            .error_for_status()
+            .context_internal_server_error("target server returned error")?
+            .json::<NodeInfoWellKnown>()
+            .await
+            .context_internal_server_error("failed to parse nodeinfo links")?;
+        let link = well_known
+            .links
+            .into_iter()
+            .next()
+            .context_internal_server_error("failed to find nodeinfo link")?;
+        let nodeinfo = data
+            .http_client
+            .get(link.href)
+            .send()
+            .await
+            .context_internal_server_error("failed to request HTTP")?
+            .error_for_status()
+            .context_internal_server_error("target server returned error")?
+            .json::<NodeInfo>()
+            .await
+            .context_internal_server_error("failed to parse nodeinfo")?;
+        Ok(nodeinfo.software.name.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, MockExecResult};
+
+    use super::*;
+
+    const INBOX: &str = "https://dead.example/inbox";
+
+    fn dead_instance() -> instance::Model {
+        let now = Utc::now().fixed_offset();
+        instance::Model {
+            host: "dead.example".to_string(),
+            last_delivered_at: None,
+            last_failed_at: Some(now),
+            failure_count: DEAD_FAILURE_COUNT,
+            dead_since: Some(now),
+            last_probed_at: None,
+            software: None,
+            created_at: now,
+        }
+    }
+
+    fn exec_result() -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }
+    }
+
+    /// Returns the statements run on the database.
+    fn statements(db: DatabaseConnection) -> Vec<String> {
+        db.into_transaction_log()
+            .into_iter()
+            .map(|transaction| format!("{:?}", transaction))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn success_revives_host() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([exec_result()])
+            .into_connection();
+        instance::Model::record_success(&Url::parse(INBOX).unwrap(), &db)
+            .await
+            .unwrap();
+
+        let statements = statements(db);
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].contains(r#"ON CONFLICT (\"host\") DO UPDATE SET"#));
+        assert!(statements[0].contains(r#"\"dead_since\" = \"excluded\".\"dead_since\""#));
+        assert!(statements[0].contains(r#"\"failure_count\" = \"excluded\".\"failure_count\""#));
+    }
+
+    #[tokio::test]
+    async fn failure_marks_host_dead_after_threshold() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_exec_results([exec_result(), exec_result()])
+            .into_connection();
+        instance::Model::record_failure(&Url::parse(INBOX).unwrap(), &db)
+            .await
+            .unwrap();
+
+        let statements = statements(db);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].contains(r#"\"failure_count\" = \"instance\".\"failure_count\" + $"#));
+        assert!(statements[1].contains(r#"UPDATE \"instance\" SET \"dead_since\""#));
+        assert!(statements[1].contains(r#"\"dead_since\" IS NULL"#));
+        assert!(statements[1].contains(r#"\"failure_count\" >= $"#));
+        assert!(statements[1].contains(&format!("Int(Some({}))", DEAD_FAILURE_COUNT)));
+    }
+
+    #[tokio::test]
+    async fn alive_probe_revives_host() {
+        let instance = dead_instance();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[instance.clone()]])
+            .into_connection();
+        instance.record_probe(true, &db).await.unwrap();
+
+        let statements = statements(db);
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].contains(r#"\"failure_count\" = $"#));
+        assert!(statements[0].contains(r#"\"dead_since\" = $"#));
+        assert!(statements[0].contains(r#"\"last_probed_at\" = $"#));
+    }
+
+    #[tokio::test]
+    async fn dead_probe_keeps_host_dead() {
+        let instance = dead_instance();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([[instance.clone()]])
+            .into_connection();
+        instance.record_probe(false, &db).await.unwrap();
+
+        let statements = statements(db);
+        assert_eq!(statements.len(), 1);
+        assert!(statements[0].contains(r#"\"last_probed_at\" = $"#));
+        assert!(!statements[0].contains(r#"\"dead_since\" = $"#));
+        assert!(!statements[0].contains(r#"\"failure_count\" = $"#));
+    }
+}
@@ -1,6 +1,9 @@
 use axum::body::Bytes;
 use mime::Mime;
-use sea_orm::{ActiveModelTrait, ActiveValue, ConnectionTrait, ModelTrait};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait,
+    ModelTrait, QueryFilter,
+};
 use ulid::Ulid;
 
 use crate::{
@@ -61,6 +64,21 @@ impl local_file::Model {
         Ok(())
     }
 
+    #[tracing::instrument(skip(db))]
+    pub async fn detach_all_from_post(post_id: Ulid, db: &impl ConnectionTrait) -> Result<()> {
+        local_file::Entity::update_many()
+            .col_expr(
+                local_file::Column::PostId,
+                Expr::value(Option::<uuid::Uuid>::None),
+            )
+            .col_expr(local_file::Column::Order, Expr::value(Option::<i16>::None))
+            .filter(local_file::Column::PostId.eq(uuid::Uuid::from(post_id)))
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+        Ok(())
+    }
+
     #[tracing::instrument(skip(db))]
     pub async fn attach_to_emoji(
         &self,
@@ -0,0 +1,150 @@
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId};
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait,
+    ModelTrait, QueryFilter, QuerySelect, TransactionTrait,
+};
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    entity::{poll, poll_option, poll_vote, user},
+    error::{Context, Error},
+    format_err,
+    queue::{Event, Update},
+    state::State,
+};
+
+impl poll::Model {
+    pub fn is_ended(&self) -> bool {
+        self.end_time
+            .map(|end_time| end_time <= Utc::now())
+            .unwrap_or(false)
+    }
+
+    /// Records a vote of the user, or of the local person if `user_id` is `None`, and updates
+    /// the vote counts.
+    pub async fn insert_vote(
+        &self,
+        order: i16,
+        user_id: Option<uuid::Uuid>,
+        uri: &Url,
+        is_new_voter: bool,
+        db: &impl ConnectionTrait,
+    ) -> Result<(), Error> {
+        let vote_activemodel = poll_vote::ActiveModel {
+            id: ActiveValue::Set(Ulid::new().into()),
+            post_id: ActiveValue::Set(self.post_id),
+            order: ActiveValue::Set(order),
+            user_id: ActiveValue::Set(user_id),
+            uri: ActiveValue::Set(uri.to_string()),
+            created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+        };
+        vote_activemodel
+            .insert(db)
+            .await
+            .context_internal_server_error("failed to insert to database")?;
+
+        poll_option::Entity::update_many()
+            .col_expr(
+                poll_option::Column::VotesCount,
+                Expr::col(poll_option::Column::VotesCount).add(1),
+            )
+            .filter(poll_option::Column::PostId.eq(self.post_id))
+            .filter(poll_option::Column::Order.eq(order))
+            .exec(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+
+        if is_new_voter {
+            poll::Entity::update_many()
+                .col_expr(
+                    poll::Column::VotersCount,
+                    Expr::col(poll::Column::VotersCount).add(1),
+                )
+                .filter(poll::Column::PostId.eq(self.post_id))
+                .exec(db)
+                .await
+                .context_internal_server_error("failed to update database")?;
+        }
+
+        Ok(())
+    }
+
+    /// Records a vote received for this local poll.
+    #[tracing::instrument(skip(data))]
+    pub async fn receive_vote(
+        &self,
+        actor: &Url,
+        uri: &Url,
+        name: &str,
+        data: &Data<State>,
+    ) -> Result<(), Error> {
+        if self.is_ended() {
+            return Err(format_err!(BAD_REQUEST, "poll has ended"));
+        }
+
+        let option = self
+            .find_related(poll_option::Entity)
+            .filter(poll_option::Column::Name.eq(name))
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .context_bad_request("poll option not found")?;
+
+        let actor: ObjectId<user::Model> = actor.clone().into();
+        let user = actor.dereference(data).await?;
+
+        let tx = data
+            .db
+            .begin()
+            .await
+            .context_internal_server_error("failed to begin database transaction")?;
+
+        let voted_orders = self
+            .find_related(poll_vote::Entity)
+            .filter(poll_vote::Column::UserId.eq(user.id))
+            .select_only()
+            .column(poll_vote::Column::Order)
+            .into_tuple::<i16>()
+            .all(&tx)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        if voted_orders.contains(&option.order) {
+            // Duplicated votes are ignored
+            return Ok(());
+        }
+        if !self.multiple && !voted_orders.is_empty() {
+            return Err(format_err!(CONFLICT, "already voted"));
+        }
+
+        self.insert_vote(
+            option.order,
+            Some(user.id),
+            uri,
+            voted_orders.is_empty(),
+            &tx,
+        )
+        .await?;
+
+        // The new results are federated by a background job, so that a burst of votes is sent as
+        // a single update
+        poll::Entity::update_many()
+            .col_expr(poll::Column::ResultsChanged, Expr::value(true))
+            .filter(poll::Column::PostId.eq(self.post_id))
+            .exec(&tx)
+            .await
+            .context_internal_server_error("failed to update database")?;
+
+        tx.commit()
+            .await
+            .context_internal_server_error("failed to commit database transaction")?;
+
+        let event = Event::Update(Update::UpdatePost {
+            post_id: self.post_id.into(),
+        });
+        event.send(&*data.db).await?;
+
+        Ok(())
+    }
+}
@@ -1,11 +1,14 @@
+use std::collections::HashMap;
+
 use activitypub_federation::{
     config::Data, fetch::object_id::ObjectId, kinds::public,
     protocol::verification::verify_domains_match, traits::Object,
 };
 use async_trait::async_trait;
+use chrono::Utc;
 use sea_orm::{
-    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait,
-    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait,
+    EntityTrait, ModelTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
 };
 use ulid::Ulid;
 use url::Url;
@@ -13,20 +16,26 @@ use url::Url;
 use crate::{
     ap::{
         announce::Announce,
-        note::{Attachment, Note, Source},
+        note::{Attachment, Note, NoteType, PollOption, PollOptionReplies, Source},
         person::LocalPerson,
-        tag::{Emoji, EmojiIcon, Hashtag, Mention, Tag},
+        tag::{Emoji, EmojiIcon, EmojiLicense, Hashtag, Link, Mention, Tag},
         NoteOrAnnounce,
     },
     config::CONFIG,
     entity::{
-        hashtag, local_file, mention, post, post_emoji, remote_file, sea_orm_active_enums, user,
+        blocked_instance, emoji, hashtag, local_file, mention, poll, poll_option, post, post_emoji,
+        post_revision, remote_file, sea_orm_active_enums, user,
     },
     error::{Context, Error},
     queue::{Event, Update},
+    sanitize::{sanitize_html, to_plain_text},
     state::State,
 };
 
+tokio::task_local! {
+    static REPLY_FETCH_DEPTH: u32;
+}
+
 fn calculate_visibility(to: &[Url], cc: &[Url]) -> sea_orm_active_enums::Visibility {
     if to.contains(&public()) {
         sea_orm_active_enums::Visibility::Public
@@ -48,6 +57,58 @@ impl post::Model {
     pub fn ap_id(&self) -> Result<Url, Error> {
         Self::ap_id_from_id(self.id.into())
     }
+
+    pub async fn save_revision(
+        &self,
+        db: &impl ConnectionTrait,
+    ) -> Result<post_revision::Model, Error> {
+        let revision_activemodel = post_revision::ActiveModel {
+            id: ActiveValue::Set(Ulid::new().into()),
+            post_id: ActiveValue::Set(self.id),
+            created_at: ActiveValue::Set(self.updated_at.unwrap_or(self.created_at)),
+            text: ActiveValue::Set(self.text.clone()),
+            title: ActiveValue::Set(self.title.clone()),
+            is_sensitive: ActiveValue::Set(self.is_sensitive),
+            source_content: ActiveValue::Set(self.source_content.clone()),
+            source_media_type: ActiveValue::Set(self.source_media_type.clone()),
+        };
+        revision_activemodel
+            .insert(db)
+            .await
+            .context_internal_server_error("failed to insert to database")
+    }
+
+    /// Resolves the reply target of a remote post.
+    ///
+    /// Fetching the target may recursively fetch its ancestors, so the number of fetched
+    /// ancestors is limited by `CONFIG.reply_fetch_depth`.
+    #[tracing::instrument(skip(data))]
+    async fn resolve_reply_id(
+        in_reply_to: ObjectId<post::Model>,
+        data: &Data<State>,
+    ) -> Option<uuid::Uuid> {
+        let depth = REPLY_FETCH_DEPTH
+            .try_with(|depth| *depth)
+            .unwrap_or_default();
+        let reply_post = if depth < CONFIG.reply_fetch_depth {
+            REPLY_FETCH_DEPTH
+                .scope(depth + 1, in_reply_to.dereference(data))
+                .await
+        } else {
+            in_reply_to.dereference_local(data).await
+        };
+        match reply_post {
+            Ok(reply_post) => Some(reply_post.id),
+            Err(error) => {
+                tracing::warn!(
+                    "failed to resolve reply target {}\n{:?}",
+                    in_reply_to,
+                    error
+                );
+                None
+            }
+        }
+    }
 }
 
 #[async_trait]
@@ -127,7 +188,7 @@ impl Object for post::Model {
                     ty: Default::default(),
                     id: uri.into(),
                     actor: user_uri,
-                    published: self.created_at,
+                    published: Some(self.created_at),
                     to,
                     cc,
                     object: repost_uri.into(),
@@ -164,6 +225,22 @@ impl Object for post::Model {
             .all(&*data.db)
             .await
             .context_internal_server_error("failed to query database")?;
+        let local_emoji_names = emojis
+            .iter()
+            .filter_map(|emoji| emoji::Model::parse_ap_id(&emoji.uri))
+            .collect::<Vec<_>>();
+        let emoji_licenses = emoji::Entity::find()
+            .filter(emoji::Column::Name.is_in(local_emoji_names))
+            .filter(emoji::Column::License.is_not_null())
+            .select_only()
+            .column(emoji::Column::Name)
+            .column(emoji::Column::License)
+            .into_tuple::<(String, String)>()
+            .all(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
         let hashtags = self
             .find_related(hashtag::Entity)
             .select_only()
@@ -256,6 +333,11 @@ impl Object for post::Model {
                         media_type: emoji.media_type.parse().ok()?,
                         url: emoji.image_url.parse().ok()?,
                     },
+                    license: emoji::Model::parse_ap_id(&emoji.uri)
+                        .and_then(|name| emoji_licenses.get(&name))
+                        .map(|license| EmojiLicense {
+                            free_text: Some(license.clone()),
+                        }),
                 }))
             }))
             .chain(hashtags.into_iter().map(|hashtag| {
@@ -264,18 +346,82 @@ impl Object for post::Model {
                     name: hashtag,
                 })
             }))
+            .chain(
+                quote_uri
+                    .clone()
+                    .map(|quote_uri| Tag::Link(Link::new_quote(quote_uri))),
+            )
             .collect::<Vec<_>>();
 
-        Ok(NoteOrAnnounce::Note(Note {
-            ty: Default::default(),
+        let poll = self
+            .find_related(poll::Entity)
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let (ty, one_of, any_of, end_time, voters_count) = if let Some(poll) = poll {
+            let options = poll
+                .find_related(poll_option::Entity)
+                .order_by_asc(poll_option::Column::Order)
+                .all(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")?;
+            let options = options
+                .into_iter()
+                .map(|option| PollOption {
+                    ty: NoteType::Note,
+                    name: option.name,
+                    replies: Some(PollOptionReplies {
+                        ty: Default::default(),
+                        total_items: option.votes_count as u64,
+                    }),
+                })
+                .collect::<Vec<_>>();
+            let voters_count = poll.voters_count.map(|count| count as u64);
+            if poll.multiple {
+                (
+                    NoteType::Question,
+                    None,
+                    Some(options),
+                    poll.end_time,
+                    voters_count,
+                )
+            } else {
+                (
+                    NoteType::Question,
+                    Some(options),
+                    None,
+                    poll.end_time,
+                    voters_count,
+                )
+            }
+        } else {
+            (NoteType::Note, None, None, None, None)
+        };
+
+        // Implementations without quote support show the quoted post as a link
+        let content = match &quote_uri {
+            Some(quote_uri) if !self.text.contains(quote_uri.as_str()) => {
+                format!(
+                    r#"{}<span class="quote-inline"><br><br>RE: <a href="{}">{}</a></span>"#,
+                    self.text, quote_uri, quote_uri
+                )
+            }
+            _ => self.text,
+        };
+
+        Ok(NoteOrAnnounce::Note(Box::new(Note {
+            ty,
             id: uri.into(),
             attributed_to: user_uri,
-            quote_url: quote_uri.map(Into::into),
-            published: self.created_at,
+            quote_url: quote_uri.clone().map(Into::into),
+            misskey_quote: quote_uri.clone().map(Into::into),
+            quote_uri: quote_uri.map(Into::into),
+            published: Some(self.created_at),
+            updated: self.updated_at,
             to,
             cc,
             summary: self.title,
-            content: self.text,
+            content,
             source: Some(Source {
                 content: self.source_content,
                 media_type: self.source_media_type,
@@ -284,7 +430,12 @@ impl Object for post::Model {
             attachment,
             sensitive: self.is_sensitive,
             tag,
-        }))
+            name: None,
+            one_of,
+            any_of,
+            end_time,
+            voters_count,
+        })))
     }
 
     #[tracing::instrument(skip(_data))]
@@ -307,25 +458,41 @@ impl Object for post::Model {
     async fn from_json(json: Self::Kind, data: &Data<Self::DataType>) -> Result<Self, Self::Error> {
         match json {
             NoteOrAnnounce::Note(json) => {
+                let json = *json;
+                blocked_instance::Model::verify_not_suspended(json.id.inner(), &*data.db).await?;
+                let reject_media =
+                    blocked_instance::Model::is_media_rejected(json.id.inner(), &*data.db).await?;
+
+                let quote_uri = json.quote();
                 let user_uri: ObjectId<user::Model> = json.attributed_to.into();
                 let user = user_uri.dereference(data).await?;
 
-                let repost_id = if let Some(repost_uri) = json.quote_url {
+                let repost_id = if let Some(repost_uri) = quote_uri {
                     let repost_post = repost_uri.dereference(data).await?;
                     Some(repost_post.id)
                 } else {
                     None
                 };
 
+                let reply_id = if let Some(in_reply_to) = json.in_reply_to {
+                    Self::resolve_reply_id(in_reply_to, data).await
+                } else {
+                    None
+                };
+
                 let visibility = calculate_visibility(&json.to, &json.cc);
+                let text = sanitize_html(&json.content);
+                let title = json.summary.as_deref().map(to_plain_text);
 
                 let mut this_activemodel = post::ActiveModel {
                     id: ActiveValue::Set(Ulid::new().into()),
-                    created_at: ActiveValue::Set(json.published),
-                    reply_id: ActiveValue::Set(None),
+                    created_at: ActiveValue::Set(
+                        json.published.unwrap_or_else(|| Utc::now().fixed_offset()),
+                    ),
+                    reply_id: ActiveValue::Set(reply_id),
                     repost_id: ActiveValue::Set(repost_id),
-                    text: ActiveValue::Set(json.content),
-                    title: ActiveValue::Set(json.summary),
+                    text: ActiveValue::Set(text.clone()),
+                    title: ActiveValue::Set(title.clone()),
                     user_id: ActiveValue::Set(Some(user.id)),
                     visibility: ActiveValue::Set(visibility),
                     is_sensitive: ActiveValue::Set(json.sensitive),
@@ -340,6 +507,8 @@ impl Object for post::Model {
                             .as_ref()
                             .and_then(|source| source.media_type.clone()),
                     ),
+                    updated_at: ActiveValue::Set(json.updated),
+                    pinned_at: ActiveValue::NotSet,
                 };
 
                 let tx = data
@@ -348,21 +517,51 @@ impl Object for post::Model {
                     .await
                     .context_internal_server_error("failed to begin database transaction")?;
 
-                let existing_id = post::Entity::find()
+                let existing = post::Entity::find()
                     .filter(post::Column::Uri.eq(json.id.inner().to_string()))
-                    .select_only()
-                    .column(post::Column::Id)
-                    .into_tuple::<uuid::Uuid>()
                     .one(&tx)
                     .await
                     .context_internal_server_error("failed to query database")?;
 
-                let this = if let Some(id) = existing_id {
+                let this = if let Some(existing) = existing {
+                    let id = existing.id;
+                    // Keep the previous contents whenever an edit is picked up, whether it
+                    // arrived as an Update or through a refetch
+                    if existing.text != text
+                        || existing.title != title
+                        || existing.is_sensitive != json.sensitive
+                    {
+                        existing.save_revision(&tx).await?;
+                    }
+
                     this_activemodel.id = ActiveValue::Unchanged(id);
-                    this_activemodel
+                    let this = this_activemodel
                         .update(&tx)
                         .await
-                        .context_internal_server_error("failed to update database")?
+                        .context_internal_server_error("failed to update database")?;
+
+                    remote_file::Entity::delete_many()
+                        .filter(remote_file::Column::PostId.eq(id))
+                        .exec(&tx)
+                        .await
+                        .context_internal_server_error("failed to delete from database")?;
+                    mention::Entity::delete_many()
+                        .filter(mention::Column::PostId.eq(id))
+                        .exec(&tx)
+                        .await
+                        .context_internal_server_error("failed to delete from database")?;
+                    post_emoji::Entity::delete_many()
+                        .filter(post_emoji::Column::PostId.eq(id))
+                        .exec(&tx)
+                        .await
+                        .context_internal_server_error("failed to delete from database")?;
+                    hashtag::Entity::delete_many()
+                        .filter(hashtag::Column::PostId.eq(id))
+                        .exec(&tx)
+                        .await
+                        .context_internal_server_error("failed to delete from database")?;
+
+                    this
                 } else {
                     this_activemodel
                         .insert(&tx)
@@ -370,8 +569,12 @@ impl Object for post::Model {
                         .context_internal_server_error("failed to insert to database")?
                 };
 
-                let remote_files = json
-                    .attachment
+                let attachment = if reject_media {
+                    Vec::new()
+                } else {
+                    json.attachment
+                };
+                let remote_files = attachment
                     .into_iter()
                     .enumerate()
                     .map(|(idx, attachment)| remote_file::ActiveModel {
@@ -410,6 +613,7 @@ impl Object for post::Model {
                                 name: ActiveValue::Set(mention.name.clone()),
                             });
                         }
+                        Tag::Emoji(_) if reject_media => {}
                         Tag::Emoji(emoji) => {
                             emojis.push(post_emoji::ActiveModel {
                                 post_id: ActiveValue::Set(this.id),
@@ -431,6 +635,7 @@ impl Object for post::Model {
                                 ),
                             });
                         }
+                        Tag::Link(_) => {}
                     }
                 }
 
@@ -474,6 +679,61 @@ impl Object for post::Model {
                         .context_internal_server_error("failed to insert to database")?;
                 }
 
+                let poll_options = json
+                    .one_of
+                    .map(|options| (false, options))
+                    .or_else(|| json.any_of.map(|options| (true, options)));
+                if let Some((multiple, options)) = poll_options {
+                    let poll_activemodel = poll::ActiveModel {
+                        post_id: ActiveValue::Set(this.id),
+                        end_time: ActiveValue::Set(json.end_time),
+                        multiple: ActiveValue::Set(multiple),
+                        voters_count: ActiveValue::Set(json.voters_count.map(|count| count as i32)),
+                        end_notified: ActiveValue::Set(false),
+                        results_changed: ActiveValue::Set(false),
+                    };
+                    poll::Entity::insert(poll_activemodel)
+                        .on_conflict(
+                            OnConflict::column(poll::Column::PostId)
+                                .update_columns([
+                                    poll::Column::EndTime,
+                                    poll::Column::Multiple,
+                                    poll::Column::VotersCount,
+                                ])
+                                .to_owned(),
+                        )
+                        .exec(&tx)
+                        .await
+                        .context_internal_server_error("failed to insert to database")?;
+
+                    poll_option::Entity::delete_many()
+                        .filter(poll_option::Column::PostId.eq(this.id))
+                        .exec(&tx)
+                        .await
+                        .context_internal_server_error("failed to delete from database")?;
+                    let poll_options = options
+                        .into_iter()
+                        .enumerate()
+                        .map(|(idx, option)| poll_option::ActiveModel {
+                            post_id: ActiveValue::Set(this.id),
+                            order: ActiveValue::Set(idx as i16),
+                            name: ActiveValue::Set(option.name),
+                            votes_count: ActiveValue::Set(
+                                option
+                                    .replies
+                                    .map(|replies| replies.total_items as i32)
+                                    .unwrap_or_default(),
+                            ),
+                        })
+                        .collect::<Vec<_>>();
+                    if !poll_options.is_empty() {
+                        poll_option::Entity::insert_many(poll_options)
+                            .exec(&tx)
+                            .await
+                            .context_internal_server_error("failed to insert to database")?;
+                    }
+                }
+
                 tx.commit()
                     .await
                     .context_internal_server_error("failed to commit database transaction")?;
@@ -481,6 +741,8 @@ impl Object for post::Model {
                 Ok(this)
             }
             NoteOrAnnounce::Announce(json) => {
+                blocked_instance::Model::verify_not_suspended(json.id.inner(), &*data.db).await?;
+
                 let user_uri: ObjectId<user::Model> = json.actor.into();
                 let user = user_uri.dereference(data).await?;
 
@@ -491,7 +753,9 @@ impl Object for post::Model {
 
                 let mut this_activemodel = post::ActiveModel {
                     id: ActiveValue::Set(Ulid::new().into()),
-                    created_at: ActiveValue::Set(json.published),
+                    created_at: ActiveValue::Set(
+                        json.published.unwrap_or_else(|| Utc::now().fixed_offset()),
+                    ),
                     reply_id: ActiveValue::Set(None),
                     repost_id: ActiveValue::Set(Some(repost_id)),
                     text: ActiveValue::Set(String::new()),
@@ -502,6 +766,8 @@ impl Object for post::Model {
                     uri: ActiveValue::Set(json.id.inner().to_string()),
                     source_content: ActiveValue::Set(None),
                     source_media_type: ActiveValue::Set(None),
+                    updated_at: ActiveValue::Set(None),
+                    pinned_at: ActiveValue::NotSet,
                 };
 
                 let tx = data
@@ -510,17 +776,14 @@ impl Object for post::Model {
                     .await
                     .context_internal_server_error("failed to begin database transaction")?;
 
-                let existing_id = post::Entity::find()
+                let existing = post::Entity::find()
                     .filter(post::Column::Uri.eq(json.id.inner().to_string()))
-                    .select_only()
-                    .column(post::Column::Id)
-                    .into_tuple::<uuid::Uuid>()
                     .one(&tx)
                     .await
                     .context_internal_server_error("failed to query database")?;
 
-                let this = if let Some(id) = existing_id {
-                    this_activemodel.id = ActiveValue::Unchanged(id);
+                let this = if let Some(existing) = existing {
+                    this_activemodel.id = ActiveValue::Unchanged(existing.id);
                     this_activemodel
                         .update(&tx)
                         .await
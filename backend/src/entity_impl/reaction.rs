@@ -105,6 +105,7 @@ impl Object for reaction::Model {
                     media_type: emoji_media_type,
                     url: emoji_image_url,
                 },
+                license: None,
             })]
         } else {
             Vec::new()
@@ -115,7 +116,8 @@ impl Object for reaction::Model {
             id: id.into(),
             actor: user_id,
             object: post_id.into(),
-            content: Some(self.content),
+            content: Some(self.content.clone()),
+            misskey_reaction: Some(self.content),
             tag,
         })
     }
@@ -151,7 +153,10 @@ impl Object for reaction::Model {
             id: Ulid::new().into(),
             user_id: Some(user.id),
             post_id: post.id,
-            content: json.content.unwrap_or_else(|| "❤️".to_string()),
+            content: json
+                .content
+                .or(json.misskey_reaction)
+                .unwrap_or_else(|| "❤️".to_string()),
             uri: json.id.inner().to_string(),
             emoji_uri,
             emoji_media_type,
@@ -0,0 +1,83 @@
+use activitypub_federation::protocol::verification::verify_domains_match;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+};
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    config::CONFIG,
+    entity::relay,
+    error::{Context, Error},
+};
+
+impl relay::Model {
+    /// Returns the ID of the follow activity sent to the relay.
+    pub fn follow_id(id: Ulid) -> Result<Url, Error> {
+        Url::parse(&format!("https://{}/relay/{}", CONFIG.public_domain, id))
+            .context_internal_server_error("failed to construct URL")
+    }
+
+    fn parse_follow_id(url: &Url) -> Option<Ulid> {
+        url.as_str()
+            .strip_prefix(&format!("https://{}/relay/", CONFIG.public_domain))
+            .and_then(|id| id.parse().ok())
+    }
+
+    /// Finds the accepted relay whose actor is `actor`.
+    pub async fn find_by_actor(
+        actor: &Url,
+        db: &impl ConnectionTrait,
+    ) -> Result<Option<Self>, Error> {
+        relay::Entity::find()
+            .filter(relay::Column::Actor.eq(actor.as_str()))
+            .filter(relay::Column::Accepted.eq(true))
+            .one(db)
+            .await
+            .context_internal_server_error("failed to query database")
+    }
+
+    /// Finds the relay that was sent the follow `follow_id`, verifying that `actor` is on the
+    /// same host as the relay inbox.
+    pub async fn find_by_follow(
+        follow_id: &Url,
+        actor: &Url,
+        db: &impl ConnectionTrait,
+    ) -> Result<Self, Error> {
+        let id = Self::parse_follow_id(follow_id).context_bad_request("unknown relay follow")?;
+        let relay = relay::Entity::find_by_id(id)
+            .one(db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .context_not_found("relay not found")?;
+
+        let inbox =
+            Url::parse(&relay.inbox).context_internal_server_error("malformed relay inbox URL")?;
+        verify_domains_match(actor, &inbox).context_bad_request("failed to verify domain")?;
+
+        Ok(relay)
+    }
+
+    /// Records the answer of the relay `actor` to the follow `follow_id`.
+    pub async fn set_accepted(
+        follow_id: &Url,
+        actor: &Url,
+        accepted: bool,
+        db: &impl ConnectionTrait,
+    ) -> Result<(), Error> {
+        let relay = Self::find_by_follow(follow_id, actor, db).await?;
+
+        let relay_activemodel = relay::ActiveModel {
+            id: ActiveValue::Unchanged(relay.id),
+            actor: ActiveValue::Set(Some(actor.to_string())),
+            accepted: ActiveValue::Set(accepted),
+            ..Default::default()
+        };
+        relay_activemodel
+            .update(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+
+        Ok(())
+    }
+}
@@ -1,28 +1,106 @@
 use activitypub_federation::{
     config::Data,
+    fetch::{object_id::ObjectId, webfinger::webfinger_resolve_actor},
     protocol::{public_key::PublicKey, verification::verify_domains_match},
     traits::{Actor, Object},
 };
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QuerySelect,
-    TransactionTrait,
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QuerySelect, TransactionTrait,
 };
 use ulid::Ulid;
 use url::Url;
 
 use crate::{
     ap::person::{ActorType, Person, PersonImage},
-    entity::user,
+    entity::{blocked_instance, user},
     error::{Context, Error},
+    sanitize::sanitize_html,
     state::State,
 };
 
+/// Actors are refetched for a rotated key at most this often, so that requests failing
+/// verification cannot make us fetch them over and over.
+const KEY_REFETCH_INTERVAL_MINUTES: i64 = 10;
+
 impl user::Model {
     pub fn display_name(&self) -> &str {
         self.name.as_deref().unwrap_or(&self.handle)
     }
+
+    pub fn also_known_as(&self) -> Vec<Url> {
+        serde_json::from_value(self.also_known_as.clone()).unwrap_or_default()
+    }
+
+    /// Returns whether the actor was fetched long enough ago to be refetched for a rotated key.
+    pub fn can_refetch_key(&self) -> bool {
+        self.last_fetched_at < Utc::now() - ChronoDuration::minutes(KEY_REFETCH_INTERVAL_MINUTES)
+    }
+
+    /// Finds the user by `handle@host` in the database, resolving it through WebFinger if it is
+    /// not known yet.
+    pub async fn find_by_acct(handle: &str, host: &str, data: &Data<State>) -> Result<Self, Error> {
+        let user = user::Entity::find()
+            .filter(user::Column::Handle.eq(handle))
+            .filter(user::Column::Host.eq(host))
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        if let Some(user) = user {
+            Ok(user)
+        } else {
+            webfinger_resolve_actor(&format!("{}@{}", handle, host), data).await
+        }
+    }
+
+    pub async fn mark_gone(&self, db: &impl ConnectionTrait) -> Result<(), Error> {
+        let user_activemodel = user::ActiveModel {
+            id: ActiveValue::Unchanged(self.id),
+            last_fetched_at: ActiveValue::Set(Utc::now().fixed_offset()),
+            gone_at: ActiveValue::Set(Some(Utc::now().fixed_offset())),
+            ..Default::default()
+        };
+        user_activemodel
+            .update(db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+        Ok(())
+    }
+
+    /// Refetches the actor from its origin, marking it as gone if the origin reports it as
+    /// deleted.
+    ///
+    /// Actors failing for other reasons are tried again after the next refetch interval.
+    #[tracing::instrument(skip(data))]
+    pub async fn refresh(&self, data: &Data<State>) -> Result<(), Error> {
+        let uri = Url::parse(&self.uri).context_internal_server_error("malformed user URI")?;
+        let object_id: ObjectId<user::Model> = uri.into();
+        if let Err(error) = object_id.dereference_forced(data).await {
+            if let Some(
+                activitypub_federation::error::Error::ObjectDeleted(_)
+                | activitypub_federation::error::Error::NotFound,
+            ) = error
+                .inner
+                .downcast_ref::<activitypub_federation::error::Error>()
+            {
+                return self.mark_gone(&*data.db).await;
+            }
+
+            let user_activemodel = user::ActiveModel {
+                id: ActiveValue::Unchanged(self.id),
+                last_fetched_at: ActiveValue::Set(Utc::now().fixed_offset()),
+                ..Default::default()
+            };
+            user_activemodel
+                .update(&*data.db)
+                .await
+                .context_internal_server_error("failed to update database")?;
+            return Err(error);
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -50,6 +128,7 @@ impl Object for user::Model {
     #[tracing::instrument(skip(_data))]
     async fn into_json(self, _data: &Data<Self::DataType>) -> Result<Self::Kind, Self::Error> {
         let id = Url::parse(&self.uri).context_internal_server_error("malformed user URI")?;
+        let also_known_as = self.also_known_as();
         Ok(Self::Kind {
             ty: if self.is_bot {
                 ActorType::Service
@@ -81,12 +160,18 @@ impl Object for user::Model {
                 .map(|inbox| Url::parse(&inbox))
                 .transpose()
                 .context_internal_server_error("malformed user shared inbox URL")?,
+            outbox: None,
+            followers: None,
+            following: None,
+            featured: None,
             public_key: PublicKey {
                 id: format!("{}#main-key", id),
                 owner: id,
                 public_key_pem: self.public_key,
             },
             manually_approves_followers: self.manually_approves_followers,
+            also_known_as,
+            moved_to: self.moved_to.and_then(|url| url.parse().ok()),
         })
     }
 
@@ -102,12 +187,16 @@ impl Object for user::Model {
 
     #[tracing::instrument(skip(data))]
     async fn from_json(json: Self::Kind, data: &Data<Self::DataType>) -> Result<Self, Self::Error> {
+        blocked_instance::Model::verify_not_suspended(json.id.inner(), &*data.db).await?;
+        let reject_media =
+            blocked_instance::Model::is_media_rejected(json.id.inner(), &*data.db).await?;
+
         let this = Self {
             id: Ulid::new().into(),
             last_fetched_at: Utc::now().fixed_offset(),
             handle: json.preferred_username,
             name: json.name,
-            description: json.summary,
+            description: json.summary.as_deref().map(sanitize_html),
             host: json
                 .id
                 .inner()
@@ -118,13 +207,23 @@ impl Object for user::Model {
             shared_inbox: json.shared_inbox.as_ref().map(Url::to_string),
             public_key: json.public_key.public_key_pem,
             uri: json.id.inner().to_string(),
-            avatar_url: json.icon.map(|image| image.url.to_string()),
-            banner_url: json.image.map(|image| image.url.to_string()),
+            avatar_url: json
+                .icon
+                .filter(|_| !reject_media)
+                .map(|image| image.url.to_string()),
+            banner_url: json
+                .image
+                .filter(|_| !reject_media)
+                .map(|image| image.url.to_string()),
             manually_approves_followers: json.manually_approves_followers,
             is_bot: match json.ty {
                 ActorType::Person => false,
                 ActorType::Service | ActorType::Application => true,
             },
+            also_known_as: serde_json::to_value(&json.also_known_as)
+                .context_bad_request("malformed alsoKnownAs")?,
+            moved_to: json.moved_to.as_ref().map(Url::to_string),
+            gone_at: None,
         };
 
         let tx = data
@@ -165,12 +264,11 @@ impl Object for user::Model {
         Ok(this)
     }
 
+    /// Called when the origin answers `410 Gone` on refetch. The actor is only marked as gone so
+    /// that its posts are kept.
     #[tracing::instrument(skip(data))]
     async fn delete(self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
-        ModelTrait::delete(self, &*data.db)
-            .await
-            .context_internal_server_error("failed to delete from database")?;
-        Ok(())
+        self.mark_gone(&*data.db).await
     }
 }
 
@@ -191,3 +289,40 @@ impl Actor for user::Model {
         self.inbox.parse().expect("malformed user inbox URL")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_fetched_minutes_ago(minutes: i64) -> user::Model {
+        user::Model {
+            id: uuid::Uuid::new_v4(),
+            last_fetched_at: (Utc::now() - ChronoDuration::minutes(minutes)).fixed_offset(),
+            handle: "alice".to_string(),
+            name: None,
+            host: "alice.example".to_string(),
+            inbox: "https://alice.example/users/alice/inbox".to_string(),
+            public_key: String::new(),
+            uri: "https://alice.example/users/alice".to_string(),
+            avatar_url: None,
+            banner_url: None,
+            shared_inbox: None,
+            manually_approves_followers: false,
+            is_bot: false,
+            description: None,
+            also_known_as: serde_json::json!([]),
+            moved_to: None,
+            gone_at: None,
+        }
+    }
+
+    #[test]
+    fn recently_fetched_key_is_not_refetched() {
+        assert!(!user_fetched_minutes_ago(1).can_refetch_key());
+    }
+
+    #[test]
+    fn stale_key_is_refetched() {
+        assert!(user_fetched_minutes_ago(KEY_REFETCH_INTERVAL_MINUTES + 1).can_refetch_key());
+    }
+}
@@ -33,8 +33,12 @@ async fn server_header_middleware<B>(req: Request<B>, next: Next<B>) -> Response
     paths(
         self::api::auth::post_login,
         self::api::auth::get_check,
+        self::api::delivery::get_failed_deliveries,
+        self::api::delivery::post_retry_deliveries,
+        self::api::delivery::post_retry_delivery,
         self::api::emoji::get_emojis,
         self::api::emoji::post_emoji,
+        self::api::emoji::post_emoji_import,
         self::api::emoji::get_emoji,
         self::api::emoji::delete_emoji,
         self::api::event::get_event_stream,
@@ -47,17 +51,34 @@ async fn server_header_middleware<B>(req: Request<B>, next: Next<B>) -> Response
         self::api::follow::delete_follow,
         self::api::follower::get_followers,
         self::api::follower::delete_follower,
+        self::api::follower::get_follow_requests,
+        self::api::follower::post_follow_request_accept,
+        self::api::follower::post_follow_request_reject,
         self::api::hashtag::get_hashtag_posts,
+        self::api::instance::get_blocked_instances,
+        self::api::instance::post_blocked_instance,
+        self::api::instance::get_blocked_instance,
+        self::api::instance::delete_blocked_instance,
+        self::api::instance::get_instance_statuses,
+        self::api::instance::get_instance_status,
+        self::api::instance::delete_instance_status,
         self::api::notification::get_notifications,
         self::api::notification::get_notification,
         self::api::post::get_posts,
         self::api::post::post_post,
         self::api::post::get_post,
+        self::api::post::patch_post,
         self::api::post::delete_post,
         self::api::post::get_post_reactions,
         self::api::post::post_post_reaction,
         self::api::post::delete_post_reaction,
+        self::api::post::post_post_pin,
+        self::api::post::delete_post_pin,
+        self::api::post::post_post_vote,
         self::api::reaction::get_reaction,
+        self::api::relay::get_relays,
+        self::api::relay::post_relay,
+        self::api::relay::delete_relay,
         self::api::report::get_reports,
         self::api::report::post_report,
         self::api::report::get_report,
@@ -66,6 +87,7 @@ async fn server_header_middleware<B>(req: Request<B>, next: Next<B>) -> Response
         self::api::setting::get_setting,
         self::api::setting::put_setting,
         self::api::setting::post_initial_setting,
+        self::api::setting::post_move,
     ),
     components(schemas(
         crate::dto::IdResponse,
@@ -79,17 +101,35 @@ async fn server_header_middleware<B>(req: Request<B>, next: Next<B>) -> Response
         crate::dto::CreateEmojiReaction,
         crate::dto::CreateReaction,
         crate::dto::Reaction,
+        crate::dto::PostRevision,
+        crate::dto::PollOption,
+        crate::dto::Poll,
         crate::dto::Post,
         crate::dto::CreatePost,
+        crate::dto::CreatePoll,
+        crate::dto::CreateVote,
+        crate::dto::UpdatePost,
         crate::dto::LocalFile,
         crate::dto::LocalEmoji,
         crate::dto::CreateEmoji,
+        crate::dto::ImportEmojiFromUri,
+        crate::dto::ImportEmojiFromPost,
+        crate::dto::ImportEmojiFromReaction,
+        crate::dto::ImportEmojiSource,
+        crate::dto::ImportEmoji,
         crate::dto::Follow,
         crate::dto::CreateFollow,
         crate::dto::Setting,
         crate::dto::Object,
         crate::dto::Report,
         crate::dto::CreateReport,
+        crate::dto::BlockSeverity,
+        crate::dto::BlockedInstance,
+        crate::dto::CreateBlockedInstance,
+        crate::dto::InstanceStatus,
+        crate::dto::Relay,
+        crate::dto::CreateRelay,
+        crate::dto::Delivery,
         crate::queue::Event,
         crate::queue::Update,
         crate::queue::Notification,
@@ -98,6 +138,7 @@ async fn server_header_middleware<B>(req: Request<B>, next: Next<B>) -> Response
         self::api::auth::PostLoginResp,
         self::api::setting::PutSettingReq,
         self::api::setting::PostInitialSettingReq,
+        self::api::setting::PostMoveReq,
     )),
     modifiers(&AccessKeyAddon),
 )]
@@ -121,9 +162,11 @@ pub async fn create_router(federation_config: FederationConfig<State>) -> anyhow
     let file = self::file::create_router();
     let well_known = self::well_known::create_router();
 
+    let emoji = self::ap::emoji::create_router();
     let follow = self::ap::follow::create_router();
     let like = self::ap::like::create_router();
     let note = self::ap::note::create_router();
+    let object = self::ap::object::create_router();
     let person = self::ap::person::create_router();
 
     let assets = self::frontend::assets::create_router();
@@ -136,9 +179,11 @@ pub async fn create_router(federation_config: FederationConfig<State>) -> anyhow
             "/nodeinfo/2.0",
             routing::get(self::nodeinfo::get_nodeinfo_2_0),
         )
+        .nest("/emoji", emoji)
         .nest("/follow", follow)
         .nest("/like", like)
         .nest("/note", note)
+        .nest("/object", object)
         .nest("/person", person)
         .route("/inbox", routing::post(self::ap::post_inbox))
         .route(
@@ -147,6 +192,7 @@ pub async fn create_router(federation_config: FederationConfig<State>) -> anyhow
         )
         .layer(TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::new().level(Level::INFO)))
         .route("/", routing::get(self::frontend::get_index))
+        .route("/tags/:name", routing::get(self::frontend::get_tag))
         .route("/*path", routing::get(self::frontend::get_not_found))
         .layer(FederationMiddleware::new(federation_config))
         .nest("/assets", assets)
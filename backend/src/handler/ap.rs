@@ -1,23 +1,111 @@
 use activitypub_federation::{
     axum::inbox::{receive_activity, ActivityData},
     config::Data,
+    error::Error as FederationError,
+    fetch::object_id::ObjectId,
     protocol::context::WithContext,
 };
+use axum::{
+    body::{Body, Bytes},
+    extract::FromRequest,
+    http::{HeaderMap, Method, Request, Uri},
+};
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    ap::{relay::RelayedActivity, Activity},
+    entity::{relay, user},
+    error::Result,
+    format_err,
+};
 
-use crate::{ap::Activity, error::Result};
+use self::signature::Signature;
 
 use super::State;
 
+pub mod emoji;
 pub mod follow;
 pub mod like;
 pub mod note;
+pub mod object;
 pub mod person;
+mod signature;
 
-#[tracing::instrument(skip(data, activity_data))]
-pub(super) async fn post_inbox(data: Data<State>, activity_data: ActivityData) -> Result<()> {
-    receive_activity::<WithContext<Activity>, crate::entity::user::Model, State>(
-        activity_data,
+#[tracing::instrument(skip(data, headers, body))]
+pub(super) async fn post_inbox(
+    data: Data<State>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<()> {
+    // Relays forward activities of other actors signed with their own key
+    if let Ok(Some(signature)) = Signature::parse_inbox(&method, &uri, &headers, &body) {
+        if let Ok(activity) = serde_json::from_slice::<RelayedActivity>(&body) {
+            if activity.actor != signature.key_owner
+                && relay::Model::find_by_actor(&signature.key_owner, &*data.db)
+                    .await?
+                    .is_some()
+            {
+                signature.verify(&data).await?;
+                return activity.receive(&data).await;
+            }
+        }
+    }
+
+    let result = receive(
+        method.clone(),
+        uri.clone(),
+        headers.clone(),
+        body.clone(),
         &data,
     )
-    .await
+    .await;
+
+    if let Err(error) = &result {
+        let is_signature_invalid = matches!(
+            error.inner.downcast_ref::<FederationError>(),
+            Some(FederationError::ActivitySignatureInvalid)
+        );
+        if is_signature_invalid {
+            if let Ok(activity) = serde_json::from_slice::<ActivityActor>(&body) {
+                // The key of a known actor may have been rotated since it was fetched
+                let actor: ObjectId<user::Model> = activity.actor.into();
+                if actor
+                    .dereference_local(&data)
+                    .await
+                    .is_ok_and(|actor| actor.can_refetch_key())
+                {
+                    actor.dereference_forced(&data).await?;
+                    return receive(method, uri, headers, body, &data).await;
+                }
+            }
+        }
+    }
+
+    result
+}
+
+#[derive(Deserialize)]
+struct ActivityActor {
+    actor: Url,
+}
+
+async fn receive(
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+    data: &Data<State>,
+) -> Result<()> {
+    let mut request = Request::new(Body::from(body));
+    *request.method_mut() = method;
+    *request.uri_mut() = uri;
+    *request.headers_mut() = headers;
+    let activity_data = ActivityData::from_request(request, &())
+        .await
+        .map_err(|_| format_err!(BAD_REQUEST, "failed to read activity"))?;
+
+    receive_activity::<WithContext<Activity>, user::Model, State>(activity_data, data).await
 }
@@ -0,0 +1,80 @@
+use activitypub_federation::{
+    axum::json::FederationJson, config::Data, protocol::context::WithContext, traits::Object,
+};
+use axum::{
+    extract,
+    http::{header, HeaderMap},
+    routing, Router,
+};
+use reqwest::StatusCode;
+use sea_orm::{EntityTrait, ModelTrait};
+
+use crate::{
+    ap::tag::Emoji,
+    entity::{emoji, local_file},
+    error::{Context, Result},
+    format_err,
+    handler::frontend::{FrontendContext, RespOrFrontend},
+    state::State,
+};
+
+use super::signature::SignedFetch;
+
+pub fn create_router() -> Router {
+    Router::new().route("/:name", routing::get(get_emoji))
+}
+
+#[tracing::instrument(skip(data, signed))]
+async fn get_emoji(
+    data: Data<State>,
+    extract::Path(name): extract::Path<String>,
+    headers: HeaderMap,
+    signed: SignedFetch,
+) -> Result<RespOrFrontend<FederationJson<WithContext<Emoji>>>> {
+    let is_activity_json = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.starts_with("application/activity+json"))
+        .unwrap_or_default();
+    if is_activity_json {
+        signed.require()?;
+    }
+
+    let this = emoji::Entity::find_by_id(name)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if let Some(this) = this {
+        if is_activity_json {
+            let this = this.into_json(&data).await?;
+            return Ok(RespOrFrontend::resp(FederationJson(
+                WithContext::new_default(this),
+            )));
+        } else {
+            let file = this
+                .find_related(local_file::Entity)
+                .one(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")?
+                .context_internal_server_error("file not found")?;
+
+            let title = format!(":{}:", this.name);
+            let ctx = FrontendContext {
+                title: Some(title.clone()),
+                description: None,
+                og_type: Some("website".to_string()),
+                og_title: Some(title),
+                og_description: None,
+                og_image: Some(file.url),
+            };
+
+            return RespOrFrontend::frontend(StatusCode::OK, &*data.db, ctx).await;
+        }
+    }
+    if is_activity_json {
+        Err(format_err!(NOT_FOUND, "emoji not found"))
+    } else {
+        let ctx = FrontendContext::site_default(&*data.db).await?;
+        RespOrFrontend::frontend(StatusCode::NOT_FOUND, &*data.db, ctx).await
+    }
+}
@@ -12,15 +12,19 @@ use crate::{
     state::State,
 };
 
+use super::signature::SignedFetch;
+
 pub fn create_router() -> Router {
     Router::new().route("/:id", routing::get(get_like))
 }
 
-#[tracing::instrument(skip(data))]
+#[tracing::instrument(skip(data, signed))]
 async fn get_like(
     data: Data<State>,
     extract::Path(id): extract::Path<Ulid>,
+    signed: SignedFetch,
 ) -> Result<FederationJson<WithContext<Like>>> {
+    signed.require()?;
     let this = reaction::Entity::find_by_id(id)
         .one(&*data.db)
         .await
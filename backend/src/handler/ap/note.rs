@@ -7,85 +7,111 @@ use axum::{
     routing, Router,
 };
 use reqwest::StatusCode;
-use sea_orm::EntityTrait;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use ulid::Ulid;
 
 use crate::{
-    ap::{person::LocalPerson, NoteOrAnnounce},
+    ap::{person::LocalPerson, CreateOrAnnounce, NoteOrAnnounce},
     entity::{post, user},
     error::{Context, Result},
     format_err,
     handler::frontend::{FrontendContext, RespOrFrontend},
+    sanitize::to_plain_text,
     state::State,
 };
 
+use super::signature::SignedFetch;
+
 pub fn create_router() -> Router {
-    Router::new().route("/:id", routing::get(get_note))
+    Router::new()
+        .route("/:id", routing::get(get_note))
+        .route("/:id/activity", routing::get(get_note_activity))
 }
 
-#[tracing::instrument(skip(data))]
+#[tracing::instrument(skip(data, signed))]
 async fn get_note(
     data: Data<State>,
     extract::Path(id): extract::Path<Ulid>,
     headers: HeaderMap,
+    signed: SignedFetch,
 ) -> Result<RespOrFrontend<FederationJson<WithContext<NoteOrAnnounce>>>> {
+    let is_activity_json = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.starts_with("application/activity+json"))
+        .unwrap_or_default();
+    if is_activity_json {
+        signed.require()?;
+    }
+
     let this = post::Entity::find_by_id(id)
         .one(&*data.db)
         .await
         .context_internal_server_error("failed to query database")?;
     if let Some(this) = this {
-        if this.visibility.is_visible() {
-            if headers
-                .get(header::ACCEPT)
-                .and_then(|v| v.to_str().ok())
-                .map(|v| v.starts_with("application/activity+json"))
-                .unwrap_or_default()
-            {
+        if is_activity_json {
+            if signed.can_view(&this, &*data.db).await? {
                 let this = this.into_json(&data).await?;
                 return Ok(RespOrFrontend::resp(FederationJson(
                     WithContext::new_default(this),
                 )));
+            }
+        } else if this.visibility.is_visible() {
+            let (name, avatar_url) = if let Some(user_id) = this.user_id {
+                let user = user::Entity::find_by_id(user_id)
+                    .one(&*data.db)
+                    .await
+                    .context_internal_server_error("failed to query database")?
+                    .context_internal_server_error("user not found")?;
+                (user.display_name().to_string(), user.avatar_url)
             } else {
-                let (name, avatar_url) = if let Some(user_id) = this.user_id {
-                    let user = user::Entity::find_by_id(user_id)
-                        .one(&*data.db)
-                        .await
-                        .context_internal_server_error("failed to query database")?
-                        .context_internal_server_error("user not found")?;
-                    (user.display_name().to_string(), user.avatar_url)
-                } else {
-                    let local_user = LocalPerson::get(&*data.db).await?;
-                    (
-                        local_user.display_name().to_string(),
-                        local_user
-                            .get_avatar_url(&*data.db)
-                            .await?
-                            .map(|url| url.to_string()),
-                    )
-                };
+                let local_user = LocalPerson::get(&*data.db).await?;
+                (
+                    local_user.display_name().to_string(),
+                    local_user
+                        .get_avatar_url(&*data.db)
+                        .await?
+                        .map(|url| url.to_string()),
+                )
+            };
 
-                let ctx = FrontendContext {
-                    title: Some(name.clone()),
-                    description: Some(this.text.clone()),
-                    og_type: Some("article".to_string()),
-                    og_title: Some(name),
-                    og_description: Some(this.text),
-                    og_image: avatar_url,
-                };
+            let description = to_plain_text(&this.text);
+            let ctx = FrontendContext {
+                title: Some(name.clone()),
+                description: Some(description.clone()),
+                og_type: Some("article".to_string()),
+                og_title: Some(name),
+                og_description: Some(description),
+                og_image: avatar_url,
+            };
 
-                return RespOrFrontend::frontend(StatusCode::OK, &*data.db, ctx).await;
-            }
+            return RespOrFrontend::frontend(StatusCode::OK, &*data.db, ctx).await;
         }
     }
-    if headers
-        .get(header::ACCEPT)
-        .and_then(|v| v.to_str().ok())
-        .map(|v| v.starts_with("application/activity+json"))
-        .unwrap_or_default()
-    {
+    if is_activity_json {
         Err(format_err!(NOT_FOUND, "post not found"))
     } else {
         let ctx = FrontendContext::site_default(&*data.db).await?;
         RespOrFrontend::frontend(StatusCode::NOT_FOUND, &*data.db, ctx).await
     }
 }
+
+#[tracing::instrument(skip(data, signed))]
+async fn get_note_activity(
+    data: Data<State>,
+    extract::Path(id): extract::Path<Ulid>,
+    signed: SignedFetch,
+) -> Result<FederationJson<WithContext<CreateOrAnnounce>>> {
+    signed.require()?;
+    let this = post::Entity::find_by_id(id)
+        .filter(post::Column::UserId.is_null())
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+    if !signed.can_view(&this, &*data.db).await? {
+        return Err(format_err!(NOT_FOUND, "post not found"));
+    }
+    let this = this.into_json(&data).await?.into_activity()?;
+    Ok(FederationJson(WithContext::new_default(this)))
+}
@@ -0,0 +1,56 @@
+use activitypub_federation::{axum::json::FederationJson, config::Data};
+use axum::{extract, routing, Router};
+use sea_orm::{EntityTrait, ModelTrait};
+use ulid::Ulid;
+
+use crate::{
+    entity::{activity, post},
+    error::{Context, Result},
+    format_err,
+    state::State,
+};
+
+use super::signature::SignedFetch;
+
+pub fn create_router() -> Router {
+    Router::new().route("/:id", routing::get(get_object))
+}
+
+#[tracing::instrument(skip(data, signed))]
+async fn get_object(
+    data: Data<State>,
+    extract::Path(id): extract::Path<Ulid>,
+    signed: SignedFetch,
+) -> Result<FederationJson<serde_json::Value>> {
+    signed.require()?;
+    let this = activity::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("object not found")?;
+
+    // Non-public activities are served to their recipients and to those who can see the post
+    // they are about
+    let is_recipient = signed
+        .actor
+        .as_ref()
+        .is_some_and(|actor| this.is_delivered_to(actor));
+    let is_visible = if this.is_public() || is_recipient {
+        true
+    } else if let Some(post) = this
+        .find_related(post::Entity)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+    {
+        signed.can_view(&post, &*data.db).await?
+    } else {
+        false
+    };
+
+    if is_visible {
+        Ok(FederationJson(this.data))
+    } else {
+        Err(format_err!(NOT_FOUND, "object not found"))
+    }
+}
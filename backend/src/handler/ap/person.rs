@@ -2,25 +2,70 @@ use activitypub_federation::{
     axum::json::FederationJson, config::Data, protocol::context::WithContext, traits::Object,
 };
 use axum::{
+    extract,
     http::{header, HeaderMap, StatusCode},
     routing, Router,
 };
+use sea_orm::{
+    ColumnTrait, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Select,
+};
+use serde::Deserialize;
+use ulid::Ulid;
+use url::Url;
 
 use crate::{
-    ap::person::{LocalPerson, Person},
-    error::Result,
+    ap::{
+        collection::{OrderedCollection, OrderedCollectionOrPage, OrderedCollectionPage},
+        person::{LocalPerson, Person},
+        CreateOrAnnounce, NoteOrAnnounce,
+    },
+    config::CONFIG,
+    entity::{follow, follower, post, sea_orm_active_enums::Visibility, setting, user},
+    error::{Context, Result},
     handler::frontend::{FrontendContext, RespOrFrontend},
+    sanitize::to_plain_text,
     state::State,
 };
 
+use super::signature::SignedFetch;
+
+const COLLECTION_PAGE_SIZE: u64 = 20;
+
 pub fn create_router() -> Router {
-    Router::new().route("/", routing::get(get_person))
+    Router::new()
+        .route("/", routing::get(get_person))
+        .route("/outbox", routing::get(get_outbox))
+        .route("/featured", routing::get(get_featured))
+        .route("/followers", routing::get(get_followers))
+        .route("/following", routing::get(get_following))
 }
 
-#[tracing::instrument(skip(data))]
+#[derive(Debug, Deserialize)]
+struct CollectionQuery {
+    #[serde(default)]
+    page: bool,
+    #[serde(default)]
+    after: Option<Ulid>,
+}
+
+fn collection_page_url(collection: &Url, after: Option<Ulid>) -> Url {
+    let mut url = collection.clone();
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("page", "true");
+        if let Some(after) = after {
+            query.append_pair("after", &after.to_string());
+        }
+    }
+    url
+}
+
+#[tracing::instrument(skip(data, signed))]
 async fn get_person(
     data: Data<State>,
     headers: HeaderMap,
+    signed: SignedFetch,
 ) -> Result<RespOrFrontend<FederationJson<WithContext<Person>>>> {
     let me = LocalPerson::get(&*data.db).await?;
     if headers
@@ -30,12 +75,18 @@ async fn get_person(
         .unwrap_or_default()
     {
         let me = me.into_json(&data).await?;
+        // Unsigned fetches still get the public key, as peers need it before they can sign
+        let me = if CONFIG.secure_mode && signed.actor.is_none() {
+            me.into_restricted()
+        } else {
+            me
+        };
         Ok(RespOrFrontend::Resp(FederationJson(
             WithContext::new_default(me),
         )))
     } else {
         let name = me.display_name().to_string();
-        let description = me.description().clone();
+        let description = me.description().as_deref().map(to_plain_text);
         let avatar_url = me
             .get_avatar_url(&*data.db)
             .await?
@@ -53,3 +104,196 @@ async fn get_person(
         RespOrFrontend::frontend(StatusCode::OK, &*data.db, ctx).await
     }
 }
+
+#[tracing::instrument(skip(data, signed))]
+async fn get_outbox(
+    data: Data<State>,
+    signed: SignedFetch,
+    extract::Query(query): extract::Query<CollectionQuery>,
+) -> Result<FederationJson<WithContext<OrderedCollectionOrPage<CreateOrAnnounce>>>> {
+    signed.require()?;
+    let outbox = LocalPerson::outbox()?;
+    let select = post::Entity::find()
+        .filter(post::Column::UserId.is_null())
+        .filter(post::Column::Visibility.is_in([Visibility::Public, Visibility::Home]));
+
+    if !query.page {
+        let total_items = select
+            .count(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        return Ok(FederationJson(WithContext::new_default(
+            OrderedCollectionOrPage::Collection(OrderedCollection {
+                ty: Default::default(),
+                id: outbox.clone(),
+                total_items,
+                first: Some(collection_page_url(&outbox, None)),
+                ordered_items: None,
+            }),
+        )));
+    }
+
+    let select = if let Some(after) = query.after {
+        select.filter(post::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        select
+    };
+    let posts = select
+        .order_by_desc(post::Column::Id)
+        .limit(COLLECTION_PAGE_SIZE)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let next = if posts.len() as u64 == COLLECTION_PAGE_SIZE {
+        posts
+            .last()
+            .map(|post| collection_page_url(&outbox, Some(post.id.into())))
+    } else {
+        None
+    };
+
+    let mut ordered_items = Vec::with_capacity(posts.len());
+    for post in posts {
+        ordered_items.push(post.into_json(&data).await?.into_activity()?);
+    }
+
+    Ok(FederationJson(WithContext::new_default(
+        OrderedCollectionOrPage::Page(OrderedCollectionPage {
+            ty: Default::default(),
+            id: collection_page_url(&outbox, query.after),
+            part_of: outbox,
+            next,
+            ordered_items,
+        }),
+    )))
+}
+
+#[tracing::instrument(skip(data, signed))]
+async fn get_featured(
+    data: Data<State>,
+    signed: SignedFetch,
+) -> Result<FederationJson<WithContext<OrderedCollection<NoteOrAnnounce>>>> {
+    signed.require()?;
+    let posts = post::Entity::find()
+        .filter(post::Column::UserId.is_null())
+        .filter(post::Column::PinnedAt.is_not_null())
+        .filter(post::Column::Visibility.is_in([Visibility::Public, Visibility::Home]))
+        .order_by_desc(post::Column::PinnedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let mut ordered_items = Vec::with_capacity(posts.len());
+    for post in posts {
+        ordered_items.push(post.into_json(&data).await?);
+    }
+
+    Ok(FederationJson(WithContext::new_default(
+        OrderedCollection {
+            ty: Default::default(),
+            id: LocalPerson::featured()?,
+            total_items: ordered_items.len() as u64,
+            first: None,
+            ordered_items: Some(ordered_items),
+        },
+    )))
+}
+
+#[tracing::instrument(skip(data, signed))]
+async fn get_followers(
+    data: Data<State>,
+    signed: SignedFetch,
+    extract::Query(query): extract::Query<CollectionQuery>,
+) -> Result<FederationJson<WithContext<OrderedCollectionOrPage<Url>>>> {
+    signed.require()?;
+    let select = follower::Entity::find()
+        .filter(follower::Column::Accepted.eq(true))
+        .inner_join(user::Entity);
+    get_user_collection(&data, LocalPerson::followers()?, select, query).await
+}
+
+#[tracing::instrument(skip(data, signed))]
+async fn get_following(
+    data: Data<State>,
+    signed: SignedFetch,
+    extract::Query(query): extract::Query<CollectionQuery>,
+) -> Result<FederationJson<WithContext<OrderedCollectionOrPage<Url>>>> {
+    signed.require()?;
+    let select = follow::Entity::find()
+        .filter(follow::Column::Accepted.eq(true))
+        .inner_join(user::Entity);
+    get_user_collection(&data, LocalPerson::following()?, select, query).await
+}
+
+async fn get_user_collection<E>(
+    data: &Data<State>,
+    collection: Url,
+    select: Select<E>,
+    query: CollectionQuery,
+) -> Result<FederationJson<WithContext<OrderedCollectionOrPage<Url>>>>
+where
+    E: EntityTrait,
+    E::Model: FromQueryResult + Sized + Send + Sync,
+{
+    let setting = setting::Model::get(&*data.db).await?;
+
+    if !query.page || setting.hide_follows {
+        let total_items = select
+            .count(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        return Ok(FederationJson(WithContext::new_default(
+            OrderedCollectionOrPage::Collection(OrderedCollection {
+                ty: Default::default(),
+                id: collection.clone(),
+                total_items,
+                first: if setting.hide_follows {
+                    None
+                } else {
+                    Some(collection_page_url(&collection, None))
+                },
+                ordered_items: None,
+            }),
+        )));
+    }
+
+    let select = if let Some(after) = query.after {
+        select.filter(user::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        select
+    };
+    let users = select
+        .select_only()
+        .column(user::Column::Id)
+        .column(user::Column::Uri)
+        .order_by_desc(user::Column::Id)
+        .limit(COLLECTION_PAGE_SIZE)
+        .into_tuple::<(uuid::Uuid, String)>()
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let next = if users.len() as u64 == COLLECTION_PAGE_SIZE {
+        users
+            .last()
+            .map(|(id, _)| collection_page_url(&collection, Some((*id).into())))
+    } else {
+        None
+    };
+
+    let ordered_items = users
+        .into_iter()
+        .filter_map(|(_, uri)| Url::parse(&uri).ok())
+        .collect::<Vec<_>>();
+
+    Ok(FederationJson(WithContext::new_default(
+        OrderedCollectionOrPage::Page(OrderedCollectionPage {
+            ty: Default::default(),
+            id: collection_page_url(&collection, query.after),
+            part_of: collection,
+            next,
+            ordered_items,
+        }),
+    )))
+}
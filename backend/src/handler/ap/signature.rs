@@ -0,0 +1,267 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId};
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequestParts, OriginalUri},
+    http::{request::Parts, uri::PathAndQuery, HeaderMap, Method, Uri},
+    RequestPartsExt,
+};
+use base64::{engine::general_purpose::STANDARD as Base64, Engine};
+use http_signature_normalization::{verify::Unverified, Config};
+use once_cell::sync::Lazy;
+use openssl::{
+    hash::{hash, MessageDigest},
+    pkey::PKey,
+    sign::Verifier,
+};
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use url::Url;
+
+use crate::{
+    config::CONFIG,
+    entity::{blocked_instance, follower, post, sea_orm_active_enums::Visibility, user},
+    error::{Context, Error, Result},
+    format_err,
+    state::State,
+};
+
+static SIGNATURE_CONFIG: Lazy<Config> =
+    Lazy::new(|| Config::new().set_expiration(Duration::from_secs(60 * 60)));
+
+/// Inbox requests must sign the digest of their body, or the body could be swapped.
+static INBOX_SIGNATURE_CONFIG: Lazy<Config> = Lazy::new(|| {
+    Config::new()
+        .set_expiration(Duration::from_secs(60 * 60))
+        .require_digest()
+});
+
+/// The actor who signed an ActivityPub GET request.
+///
+/// Signatures are only verified in secure mode, so `actor` is always `None` otherwise.
+pub struct SignedFetch {
+    pub actor: Option<user::Model>,
+}
+
+impl SignedFetch {
+    /// Returns an error if secure mode is enabled and the request is not signed.
+    pub fn require(&self) -> Result<()> {
+        if CONFIG.secure_mode && self.actor.is_none() {
+            Err(format_err!(UNAUTHORIZED, "request is not signed"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns whether the signer is an accepted follower of the local user.
+    pub async fn is_follower(&self, db: &impl ConnectionTrait) -> Result<bool> {
+        if let Some(actor) = &self.actor {
+            let count = follower::Entity::find_by_id(actor.id)
+                .filter(follower::Column::Accepted.eq(true))
+                .count(db)
+                .await
+                .context_internal_server_error("failed to query database")?;
+            Ok(count > 0)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Returns whether the signer is allowed to fetch the post.
+    /// Followers-only posts of the local user are served to its followers.
+    pub async fn can_view(&self, post: &post::Model, db: &impl ConnectionTrait) -> Result<bool> {
+        if post.visibility.is_visible() {
+            Ok(true)
+        } else if post.visibility == Visibility::Followers && post.user_id.is_none() {
+            self.is_follower(db).await
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+/// A parsed HTTP signature that is not verified yet.
+pub struct Signature {
+    unverified: Unverified,
+    /// Owner of the key the request is signed with.
+    pub key_owner: Url,
+}
+
+impl Signature {
+    /// Parses the HTTP signature of the request, returning `None` if it is not signed.
+    pub fn parse(method: &Method, uri: &Uri, headers: &HeaderMap) -> Result<Option<Self>> {
+        Self::parse_with(&SIGNATURE_CONFIG, method, uri, headers)
+    }
+
+    /// Parses the HTTP signature of an inbox request, returning `None` if it is not signed.
+    ///
+    /// The signature must cover the `Digest` header, which is checked against the body.
+    pub fn parse_inbox(
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<Option<Self>> {
+        let signature = Self::parse_with(&INBOX_SIGNATURE_CONFIG, method, uri, headers)?;
+        if signature.is_some() {
+            verify_digest(headers, body)?;
+        }
+        Ok(signature)
+    }
+
+    fn parse_with(
+        config: &Config,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+    ) -> Result<Option<Self>> {
+        let mut header_map = BTreeMap::<String, String>::new();
+        for (name, value) in headers {
+            if let Ok(value) = value.to_str() {
+                header_map.insert(name.to_string(), value.to_string());
+            }
+        }
+        if !header_map.contains_key("signature") {
+            return Ok(None);
+        }
+
+        let path_and_query = uri.path_and_query().map(PathAndQuery::as_str).unwrap_or("");
+        let unverified = config
+            .begin_verify(method.as_str(), path_and_query, header_map)
+            .context_unauthorized("invalid signature")?;
+
+        let mut key_owner =
+            Url::parse(unverified.key_id()).context_unauthorized("malformed signature key ID")?;
+        key_owner.set_fragment(None);
+
+        Ok(Some(Signature {
+            unverified,
+            key_owner,
+        }))
+    }
+
+    /// Verifies the signature against the public key of its owner, and returns the owner.
+    pub async fn verify(self, data: &Data<State>) -> Result<user::Model> {
+        blocked_instance::Model::verify_not_suspended(&self.key_owner, &*data.db).await?;
+        let actor_id: ObjectId<user::Model> = self.key_owner.clone().into();
+        let actor = actor_id.dereference(data).await?;
+
+        if self.verify_with(&actor)? {
+            return Ok(actor);
+        }
+
+        // The key may have been rotated since the actor was fetched
+        if actor.can_refetch_key() {
+            let actor = actor_id.dereference_forced(data).await?;
+            if self.verify_with(&actor)? {
+                return Ok(actor);
+            }
+        }
+        Err(format_err!(UNAUTHORIZED, "invalid signature"))
+    }
+
+    fn verify_with(&self, actor: &user::Model) -> Result<bool> {
+        self.unverified
+            .verify(|signature, signing_string| -> Result<bool> {
+                let public_key = PKey::public_key_from_pem(actor.public_key.as_bytes())
+                    .context_internal_server_error("malformed public key")?;
+                let mut verifier = Verifier::new(MessageDigest::sha256(), &public_key)
+                    .context_internal_server_error("failed to create verifier")?;
+                verifier
+                    .update(signing_string.as_bytes())
+                    .context_internal_server_error("failed to update verifier")?;
+                let signature = Base64
+                    .decode(signature)
+                    .context_unauthorized("malformed signature")?;
+                verifier
+                    .verify(&signature)
+                    .context_unauthorized("invalid signature")
+            })
+    }
+}
+
+/// Returns an error if no SHA-256 digest in the `Digest` header matches the body.
+fn verify_digest(headers: &HeaderMap, body: &[u8]) -> Result<()> {
+    let digest = headers
+        .get("digest")
+        .and_then(|value| value.to_str().ok())
+        .context_unauthorized("missing digest")?;
+    let expected = Base64.encode(
+        hash(MessageDigest::sha256(), body).context_internal_server_error("failed to hash body")?,
+    );
+    let is_valid = digest
+        .split(',')
+        .filter_map(|part| part.trim().split_once('='))
+        .any(|(algorithm, value)| algorithm.eq_ignore_ascii_case("SHA-256") && value == expected);
+    if is_valid {
+        Ok(())
+    } else {
+        Err(format_err!(UNAUTHORIZED, "invalid digest"))
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for SignedFetch
+where
+    S: Clone + Send + Sync + 'static,
+{
+    type Rejection = Error;
+
+    #[tracing::instrument(skip(parts, _state))]
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        if !CONFIG.secure_mode {
+            return Ok(SignedFetch { actor: None });
+        }
+
+        // Nested routers only see the stripped path, but the signature covers the original one
+        let uri = parts
+            .extensions
+            .get::<OriginalUri>()
+            .map(|uri| uri.0.clone())
+            .unwrap_or_else(|| parts.uri.clone());
+        let signature = Signature::parse(&parts.method, &uri, &parts.headers)?;
+
+        if let Some(signature) = signature {
+            let data = parts
+                .extract::<Data<State>>()
+                .await
+                .map_err(|(code, message)| Error::new(code, message))?;
+            let actor = signature.verify(&data).await?;
+            Ok(SignedFetch { actor: Some(actor) })
+        } else {
+            Ok(SignedFetch { actor: None })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    const BODY: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.";
+
+    fn headers_with_digest(digest: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("digest", HeaderValue::from_static(digest));
+        headers
+    }
+
+    #[test]
+    fn matching_digest_is_accepted() {
+        let headers = headers_with_digest("SHA-256=lzFT+G7C2hdI5j8M+FuJg1tC+O6AGMVJhooTCKGfbKM=");
+        assert!(verify_digest(&headers, BODY.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn swapped_body_is_rejected() {
+        let headers = headers_with_digest("SHA-256=lzFT+G7C2hdI5j8M+FuJg1tC+O6AGMVJhooTCKGfbKM=");
+        assert!(verify_digest(&headers, b"{\"type\":\"Delete\"}").is_err());
+    }
+
+    #[test]
+    fn missing_digest_is_rejected() {
+        assert!(verify_digest(&HeaderMap::new(), BODY.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use ulid::Ulid;
+
+use crate::{
+    dto::Delivery,
+    entity::delivery,
+    error::{Context, Result},
+    format_err,
+    state::State,
+};
+
+use super::auth::Access;
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::get(get_failed_deliveries))
+        .route("/retry", routing::post(post_retry_deliveries))
+        .route("/:id/retry", routing::post(post_retry_delivery))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/delivery",
+    responses(
+        (status = 200, body = Vec<Delivery>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn get_failed_deliveries(data: Data<State>, _access: Access) -> Result<Json<Vec<Delivery>>> {
+    let deliveries = delivery::Entity::find()
+        .filter(delivery::Column::Failed.eq(true))
+        .order_by_desc(delivery::Column::CreatedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let deliveries = deliveries
+        .into_iter()
+        .map(Delivery::from_model)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Json(deliveries))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/delivery/retry",
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn post_retry_deliveries(data: Data<State>, _access: Access) -> Result<()> {
+    delivery::Model::retry_failed(None, &*data.db).await?;
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/delivery/{id}/retry",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn post_retry_delivery(
+    data: Data<State>,
+    _access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    let retried_count = delivery::Model::retry_failed(Some(id), &*data.db).await?;
+    if retried_count == 0 {
+        return Err(format_err!(NOT_FOUND, "failed delivery not found"));
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use activitypub_federation::config::Data;
+use activitypub_federation::{config::Data, fetch::fetch_object_http};
 use axum::{extract, routing, Json, Router};
 use chrono::Utc;
 
@@ -6,10 +6,15 @@ use sea_orm::{
     ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait,
     QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
 };
+use url::Url;
 
 use crate::{
-    dto::{CreateEmoji, LocalEmoji, NameResponse, TimestampPaginationQuery},
-    entity::{emoji, local_file},
+    ap::tag::Emoji,
+    dto::{
+        CreateEmoji, ImportEmoji, ImportEmojiSource, LocalEmoji, NameResponse,
+        TimestampPaginationQuery,
+    },
+    entity::{emoji, local_file, post_emoji, reaction},
     error::{Context, Result},
     format_err,
     state::State,
@@ -20,6 +25,7 @@ use super::auth::Access;
 pub(super) fn create_router() -> Router {
     Router::new()
         .route("/", routing::get(get_emojis).post(post_emoji))
+        .route("/import", routing::post(post_emoji_import))
         .route("/:name", routing::get(get_emoji).delete(delete_emoji))
 }
 
@@ -102,6 +108,8 @@ async fn post_emoji(
     let emoji_activemodel = emoji::ActiveModel {
         name: ActiveValue::Set(req.name),
         created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+        original_uri: ActiveValue::Set(None),
+        license: ActiveValue::Set(req.license),
     };
 
     let emoji = emoji_activemodel
@@ -118,6 +126,94 @@ async fn post_emoji(
     Ok(Json(NameResponse { name: emoji.name }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/emoji/import",
+    request_body = ImportEmoji,
+    responses(
+        (status = 200, body = NameResponse),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn post_emoji_import(
+    data: Data<State>,
+    _access: Access,
+    Json(req): Json<ImportEmoji>,
+) -> Result<Json<NameResponse>> {
+    let (image_url, media_type, original_uri, license) = match req.source {
+        ImportEmojiSource::Uri(source) => {
+            let emoji = fetch_object_http::<_, Emoji>(&source.uri, &data)
+                .await?
+                .object;
+            (
+                emoji.icon.url,
+                emoji.icon.media_type,
+                emoji.id,
+                emoji.license.and_then(|license| license.free_text),
+            )
+        }
+        ImportEmojiSource::Post(source) => {
+            let emoji = post_emoji::Entity::find_by_id((
+                uuid::Uuid::from(source.post_id),
+                source.emoji_name,
+            ))
+            .one(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .context_not_found("emoji not found")?;
+            (
+                Url::parse(&emoji.image_url)
+                    .context_internal_server_error("malformed emoji image URL")?,
+                emoji
+                    .media_type
+                    .parse()
+                    .context_internal_server_error("malformed emoji media type")?,
+                Url::parse(&emoji.uri).context_internal_server_error("malformed emoji URI")?,
+                None,
+            )
+        }
+        ImportEmojiSource::Reaction(source) => {
+            let reaction = reaction::Entity::find_by_id(source.reaction_id)
+                .one(&*data.db)
+                .await
+                .context_internal_server_error("failed to query database")?
+                .context_not_found("reaction not found")?;
+            if let (Some(emoji_uri), Some(emoji_media_type), Some(emoji_image_url)) = (
+                reaction.emoji_uri,
+                reaction.emoji_media_type,
+                reaction.emoji_image_url,
+            ) {
+                (
+                    Url::parse(&emoji_image_url)
+                        .context_internal_server_error("malformed emoji image URL")?,
+                    emoji_media_type
+                        .parse()
+                        .context_internal_server_error("malformed emoji media type")?,
+                    Url::parse(&emoji_uri).context_internal_server_error("malformed emoji URI")?,
+                    None,
+                )
+            } else {
+                return Err(format_err!(BAD_REQUEST, "reaction is not a custom emoji"));
+            }
+        }
+    };
+
+    let emoji = emoji::Model::import(
+        req.name,
+        image_url,
+        media_type,
+        original_uri,
+        req.license.or(license),
+        &data,
+    )
+    .await?;
+
+    Ok(Json(NameResponse { name: emoji.name }))
+}
+
 #[utoipa::path(
     get,
     path = "/api/emoji/{name}",
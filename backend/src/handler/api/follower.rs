@@ -1,12 +1,13 @@
-use activitypub_federation::config::Data;
+use activitypub_federation::{config::Data, traits::Object};
 use axum::{extract, routing, Json, Router};
 use sea_orm::{
-    ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, QueryFilter, QueryOrder,
+    QuerySelect, TransactionTrait,
 };
 use ulid::Ulid;
 
 use crate::{
-    ap::follow::FollowReject,
+    ap::follow::{FollowAccept, FollowReject},
     dto::{IdPaginationQuery, User},
     entity::{follower, user},
     error::{Context, Result},
@@ -19,6 +20,15 @@ pub(super) fn create_router() -> Router {
     Router::new()
         .route("/", routing::get(get_followers))
         .route("/:id", routing::delete(delete_follower))
+        .route("/request", routing::get(get_follow_requests))
+        .route(
+            "/request/:id/accept",
+            routing::post(post_follow_request_accept),
+        )
+        .route(
+            "/request/:id/reject",
+            routing::post(post_follow_request_reject),
+        )
 }
 
 #[utoipa::path(
@@ -38,7 +48,9 @@ async fn get_followers(
     _access: Access,
     extract::Query(query): extract::Query<IdPaginationQuery>,
 ) -> Result<Json<Vec<User>>> {
-    let pagination_query = follower::Entity::find().find_also_related(user::Entity);
+    let pagination_query = follower::Entity::find()
+        .filter(follower::Column::Accepted.eq(true))
+        .find_also_related(user::Entity);
     let pagination_query = if let Some(after) = query.after {
         pagination_query.filter(user::Column::Id.lt(uuid::Uuid::from(after)))
     } else {
@@ -91,8 +103,7 @@ async fn delete_follower(
         .context_bad_request("follower not found")?;
     let user = user.context_internal_server_error("user not found")?;
 
-    follower
-        .delete(&tx)
+    ModelTrait::delete(follower, &tx)
         .await
         .context_internal_server_error("failed to delete from database")?;
 
@@ -117,3 +128,129 @@ async fn delete_follower(
 
     Ok(())
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/follower/request",
+    params(IdPaginationQuery),
+    responses(
+        (status = 200, body = Vec<User>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn get_follow_requests(
+    data: Data<State>,
+    _access: Access,
+    extract::Query(query): extract::Query<IdPaginationQuery>,
+) -> Result<Json<Vec<User>>> {
+    let pagination_query = follower::Entity::find()
+        .filter(follower::Column::Accepted.eq(false))
+        .find_also_related(user::Entity);
+    let pagination_query = if let Some(after) = query.after {
+        pagination_query.filter(user::Column::Id.lt(uuid::Uuid::from(after)))
+    } else {
+        pagination_query
+    };
+    let requests = pagination_query
+        .order_by_desc(user::Column::Id)
+        .limit(query.size)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let requests = requests
+        .into_iter()
+        .filter_map(|(_, user)| user)
+        .filter_map(|user| User::from_model(user).ok())
+        .collect::<Vec<_>>();
+    Ok(Json(requests))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/follower/request/{id}/accept",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn post_follow_request_accept(
+    data: Data<State>,
+    extract::Path(id): extract::Path<Ulid>,
+    _access: Access,
+) -> Result<()> {
+    let follower = follower::Entity::find_by_id(id)
+        .filter(follower::Column::Accepted.eq(false))
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("follow request not found")?;
+
+    let mut follower_activemodel: follower::ActiveModel = follower.into();
+    follower_activemodel.accepted = ActiveValue::Set(true);
+    let follower = follower_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    let follow = follower.into_json(&data).await?;
+    let accept = FollowAccept::new(follow)?;
+    accept.send(&data).await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/follower/request/{id}/reject",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn post_follow_request_reject(
+    data: Data<State>,
+    extract::Path(id): extract::Path<Ulid>,
+    _access: Access,
+) -> Result<()> {
+    let (follower, user) = follower::Entity::find_by_id(id)
+        .filter(follower::Column::Accepted.eq(false))
+        .find_also_related(user::Entity)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("follow request not found")?;
+    let user = user.context_internal_server_error("user not found")?;
+
+    let follow = follower.clone().into_json(&data).await?;
+
+    ModelTrait::delete(follower, &*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    let reject = FollowReject::from_follow(follow)?;
+    reject
+        .send(
+            &data,
+            user.inbox
+                .parse()
+                .context_internal_server_error("malformed user inbox URL")?,
+        )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,249 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use chrono::Utc;
+use sea_orm::{sea_query::OnConflict, ActiveValue, EntityTrait, QueryOrder};
+use url::Url;
+
+use crate::{
+    dto::{BlockedInstance, CreateBlockedInstance, InstanceStatus},
+    entity::{blocked_instance, instance},
+    error::{Context, Result},
+    state::State,
+};
+
+use super::auth::Access;
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route(
+            "/block",
+            routing::get(get_blocked_instances).post(post_blocked_instance),
+        )
+        .route(
+            "/block/:host",
+            routing::get(get_blocked_instance).delete(delete_blocked_instance),
+        )
+        .route("/status", routing::get(get_instance_statuses))
+        .route(
+            "/status/:host",
+            routing::get(get_instance_status).delete(delete_instance_status),
+        )
+}
+
+fn normalize_host(host: &str) -> Result<String> {
+    let url =
+        Url::parse(&format!("https://{}", host.trim())).context_bad_request("invalid host")?;
+    let host = url.host_str().context_bad_request("invalid host")?;
+    Ok(host.to_lowercase())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/instance/block",
+    responses(
+        (status = 200, body = Vec<BlockedInstance>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn get_blocked_instances(
+    data: Data<State>,
+    _access: Access,
+) -> Result<Json<Vec<BlockedInstance>>> {
+    let blocked_instances = blocked_instance::Entity::find()
+        .order_by_desc(blocked_instance::Column::CreatedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let blocked_instances = blocked_instances
+        .into_iter()
+        .map(BlockedInstance::from_model)
+        .collect::<Vec<_>>();
+    Ok(Json(blocked_instances))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/instance/block",
+    request_body = CreateBlockedInstance,
+    responses(
+        (status = 200, body = BlockedInstance),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn post_blocked_instance(
+    data: Data<State>,
+    _access: Access,
+    Json(req): Json<CreateBlockedInstance>,
+) -> Result<Json<BlockedInstance>> {
+    let host = normalize_host(&req.host)?;
+
+    let blocked_instance_activemodel = blocked_instance::ActiveModel {
+        host: ActiveValue::Set(host.clone()),
+        severity: ActiveValue::Set(req.severity.into()),
+        reason: ActiveValue::Set(req.reason),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+    };
+    blocked_instance::Entity::insert(blocked_instance_activemodel)
+        .on_conflict(
+            OnConflict::column(blocked_instance::Column::Host)
+                .update_columns([
+                    blocked_instance::Column::Severity,
+                    blocked_instance::Column::Reason,
+                ])
+                .to_owned(),
+        )
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    let blocked_instance = blocked_instance::Entity::find_by_id(host)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_internal_server_error("failed to find blocked instance")?;
+
+    Ok(Json(BlockedInstance::from_model(blocked_instance)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/instance/block/{host}",
+    params(
+        ("host" = String, Path, description = "Host of the blocked instance"),
+    ),
+    responses(
+        (status = 200, body = BlockedInstance),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn get_blocked_instance(
+    data: Data<State>,
+    _access: Access,
+    extract::Path(host): extract::Path<String>,
+) -> Result<Json<BlockedInstance>> {
+    let host = normalize_host(&host)?;
+    let blocked_instance = blocked_instance::Entity::find_by_id(host)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("blocked instance not found")?;
+    Ok(Json(BlockedInstance::from_model(blocked_instance)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/instance/block/{host}",
+    params(
+        ("host" = String, Path, description = "Host of the blocked instance"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn delete_blocked_instance(
+    data: Data<State>,
+    _access: Access,
+    extract::Path(host): extract::Path<String>,
+) -> Result<()> {
+    let host = normalize_host(&host)?;
+    blocked_instance::Entity::delete_by_id(host)
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/instance/status",
+    responses(
+        (status = 200, body = Vec<InstanceStatus>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn get_instance_statuses(
+    data: Data<State>,
+    _access: Access,
+) -> Result<Json<Vec<InstanceStatus>>> {
+    let instances = instance::Entity::find()
+        .order_by_asc(instance::Column::Host)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let instances = instances
+        .into_iter()
+        .map(InstanceStatus::from_model)
+        .collect::<Vec<_>>();
+    Ok(Json(instances))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/instance/status/{host}",
+    params(
+        ("host" = String, Path, description = "Host of the instance"),
+    ),
+    responses(
+        (status = 200, body = InstanceStatus),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn get_instance_status(
+    data: Data<State>,
+    _access: Access,
+    extract::Path(host): extract::Path<String>,
+) -> Result<Json<InstanceStatus>> {
+    let host = normalize_host(&host)?;
+    let instance = instance::Entity::find_by_id(host)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("instance not found")?;
+    Ok(Json(InstanceStatus::from_model(instance)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/instance/status/{host}",
+    params(
+        ("host" = String, Path, description = "Host of the instance"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn delete_instance_status(
+    data: Data<State>,
+    _access: Access,
+    extract::Path(host): extract::Path<String>,
+) -> Result<()> {
+    let host = normalize_host(&host)?;
+    instance::Entity::delete_by_id(host)
+        .exec(&*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    Ok(())
+}
@@ -1,32 +1,59 @@
-use activitypub_federation::{config::Data, traits::Object};
+use std::collections::HashMap;
+
+use activitypub_federation::{config::Data, protocol::context::WithContext, traits::Object};
 use axum::{extract, routing, Json, Router};
 use chrono::Utc;
 use futures_util::{stream::FuturesOrdered, TryStreamExt};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait,
-    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
 };
 use ulid::Ulid;
 use url::Url;
 
 use crate::{
-    ap::{delete::Delete, like::Like, undo::Undo},
-    dto::{CreatePost, CreateReaction, IdPaginationQuery, IdResponse, Post, Reaction, Visibility},
+    ap::{
+        announce::Announce,
+        delete::Delete,
+        generate_object_id,
+        note::{CreateNote, Note, UpdateNote},
+        pin::{Add, Remove},
+        undo::Undo,
+        NoteOrAnnounce,
+    },
+    config::CONFIG,
+    dto::{
+        CreatePoll, CreatePost, CreateReaction, CreateVote, IdPaginationQuery, IdResponse, Mention,
+        Post, Reaction, UpdatePost, Visibility,
+    },
     entity::{
-        emoji, hashtag, local_file, mention, post, post_emoji, reaction, sea_orm_active_enums, user,
+        activity, emoji, hashtag, local_file, mention, poll, poll_option, poll_vote, post,
+        post_emoji, reaction, sea_orm_active_enums, user,
     },
     error::{Context, Result},
     format_err,
+    mfm::{render_html, Extracted, MFM_MEDIA_TYPE},
     state::State,
-    util::get_follower_inboxes,
+    util::get_post_inboxes,
 };
 
 use super::auth::Access;
 
+const MAX_PINNED_POSTS: u64 = 5;
+const MAX_POLL_OPTIONS: usize = 10;
+
 pub(super) fn create_router() -> Router {
     Router::new()
         .route("/", routing::get(get_posts).post(post_post))
-        .route("/:id", routing::get(get_post).delete(delete_post))
+        .route(
+            "/:id",
+            routing::get(get_post).patch(patch_post).delete(delete_post),
+        )
+        .route(
+            "/:id/pin",
+            routing::post(post_post_pin).delete(delete_post_pin),
+        )
+        .route("/:id/vote", routing::post(post_post_vote))
         .route(
             "/:id/reaction",
             routing::get(get_post_reactions)
@@ -90,6 +117,22 @@ async fn post_post(
     _access: Access,
     Json(req): Json<CreatePost>,
 ) -> Result<Json<IdResponse>> {
+    let contents = resolve_post_contents(
+        &req.text,
+        req.source_content.as_deref(),
+        req.mentions,
+        req.emojis,
+        req.hashtags,
+        &data,
+    )
+    .await?;
+    let (text, source_media_type) = render_post_text(
+        req.text,
+        req.source_content.as_deref(),
+        req.source_media_type,
+        &contents,
+    )?;
+
     let tx = data
         .db
         .begin()
@@ -114,13 +157,21 @@ async fn post_post(
             return Err(format_err!(NOT_FOUND, "repost target post not found"));
         }
     }
-
-    let emojis = emoji::Entity::find()
-        .filter(emoji::Column::Name.is_in(req.emojis))
-        .find_also_related(local_file::Entity)
-        .all(&tx)
-        .await
-        .context_internal_server_error("failed to query database")?;
+    if let Some(poll) = &req.poll {
+        if req.repost_id.is_some() && text.is_empty() {
+            return Err(format_err!(BAD_REQUEST, "repost cannot have poll"));
+        }
+        if poll.options.len() < 2 || poll.options.len() > MAX_POLL_OPTIONS {
+            return Err(format_err!(BAD_REQUEST, "invalid number of poll options"));
+        }
+        if poll
+            .end_time
+            .map(|end_time| end_time <= Utc::now())
+            .unwrap_or(false)
+        {
+            return Err(format_err!(BAD_REQUEST, "poll end time is in the past"));
+        }
+    }
 
     let id = Ulid::new();
     let post_activemodel = post::ActiveModel {
@@ -128,7 +179,7 @@ async fn post_post(
         created_at: ActiveValue::Set(Utc::now().fixed_offset()),
         reply_id: ActiveValue::Set(req.reply_id.map(Into::into)),
         repost_id: ActiveValue::Set(req.repost_id.map(Into::into)),
-        text: ActiveValue::Set(req.text),
+        text: ActiveValue::Set(text),
         title: ActiveValue::Set(req.title),
         user_id: ActiveValue::Set(None),
         visibility: ActiveValue::Set(match req.visibility {
@@ -139,23 +190,151 @@ async fn post_post(
         }),
         is_sensitive: ActiveValue::Set(req.is_sensitive),
         uri: ActiveValue::Set(post::Model::ap_id_from_id(id)?.to_string()),
-        source_content: ActiveValue::Set(None), // TODO: Set to MFM?
-        source_media_type: ActiveValue::Set(None),
+        source_content: ActiveValue::Set(req.source_content),
+        source_media_type: ActiveValue::Set(source_media_type),
+        updated_at: ActiveValue::Set(None),
+        pinned_at: ActiveValue::Set(None),
     };
     let post = post_activemodel
         .insert(&tx)
         .await
         .context_internal_server_error("failed to insert to database")?;
 
-    for (idx, local_file_id) in req.files.into_iter().enumerate() {
+    attach_post_contents(&post, req.files, &contents, &tx).await?;
+
+    if let Some(poll) = req.poll {
+        create_poll(&post, poll, &tx).await?;
+    }
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commmit database transaction")?;
+
+    let post_id = post.id.into();
+    let visibility = post.visibility.clone();
+
+    let post = post.into_json(&data).await?;
+
+    let inboxes = get_post_inboxes(
+        &visibility,
+        contents
+            .mentions
+            .into_iter()
+            .map(|mention| mention.user_uri)
+            .collect(),
+        &*data.db,
+    )
+    .await?;
+
+    post.send(&data, inboxes).await?;
+
+    Ok(Json(IdResponse { id: post_id }))
+}
+
+/// Mentions, custom emojis and hashtags of a post.
+struct PostContents {
+    mentions: Vec<Mention>,
+    emojis: Vec<String>,
+    hashtags: Vec<String>,
+}
+
+/// Parses the mentions, custom emojis and hashtags from the text and its source, unless the
+/// client gave them explicitly.
+///
+/// Mentioned users are resolved through WebFinger if they are not known yet. Mentions that
+/// cannot be resolved are left as plain text.
+async fn resolve_post_contents(
+    text: &str,
+    source_content: Option<&str>,
+    mentions: Option<Vec<Mention>>,
+    emojis: Option<Vec<String>>,
+    hashtags: Option<Vec<String>>,
+    data: &Data<State>,
+) -> Result<PostContents> {
+    let mut extracted = Extracted::from_text(text);
+    if let Some(source_content) = source_content {
+        extracted.extend(source_content);
+    }
+
+    let mentions = if let Some(mentions) = mentions {
+        mentions
+    } else {
+        let mut mentions = Vec::new();
+        for mention in extracted.mentions {
+            if mention.host == CONFIG.public_domain {
+                continue;
+            }
+            match user::Model::find_by_acct(&mention.handle, &mention.host, data).await {
+                Ok(user) => {
+                    mentions.push(Mention {
+                        user_uri: Url::parse(&user.uri)
+                            .context_internal_server_error("malformed user URI")?,
+                        name: format!("@{}@{}", mention.handle, mention.host),
+                    });
+                }
+                Err(error) => {
+                    tracing::info!(
+                        "failed to resolve mention @{}@{}\n{:?}",
+                        mention.handle,
+                        mention.host,
+                        error
+                    );
+                }
+            }
+        }
+        mentions
+    };
+
+    Ok(PostContents {
+        mentions,
+        emojis: emojis.unwrap_or(extracted.emojis),
+        hashtags: hashtags.unwrap_or(extracted.hashtags),
+    })
+}
+
+/// Renders the text from the source if it is given, and returns the text with the media type of
+/// the source. Without the source, the text is taken as is.
+fn render_post_text(
+    text: String,
+    source_content: Option<&str>,
+    source_media_type: Option<String>,
+    contents: &PostContents,
+) -> Result<(String, Option<String>)> {
+    if let Some(source_content) = source_content {
+        let source_media_type = source_media_type.unwrap_or_else(|| MFM_MEDIA_TYPE.to_string());
+        let mentions = contents
+            .mentions
+            .iter()
+            .map(|mention| (mention.name.clone(), mention.user_uri.clone()))
+            .collect::<HashMap<_, _>>();
+        let text = render_html(source_content, &source_media_type, &mentions)?;
+        Ok((text, Some(source_media_type)))
+    } else {
+        Ok((text, source_media_type))
+    }
+}
+
+async fn attach_post_contents(
+    post: &post::Model,
+    files: Vec<Ulid>,
+    contents: &PostContents,
+    db: &impl ConnectionTrait,
+) -> Result<()> {
+    for (idx, local_file_id) in files.into_iter().enumerate() {
         let file = local_file::Entity::find_by_id(local_file_id)
-            .one(&tx)
+            .one(db)
             .await
             .context_internal_server_error("failed to query database")?
             .context_not_found("file not found")?;
-        file.attach_to_post(post.id.into(), idx as u8, &tx).await?;
+        file.attach_to_post(post.id.into(), idx as u8, db).await?;
     }
 
+    let emojis = emoji::Entity::find()
+        .filter(emoji::Column::Name.is_in(contents.emojis.iter().map(String::as_str)))
+        .find_also_related(local_file::Entity)
+        .all(db)
+        .await
+        .context_internal_server_error("failed to query database")?;
     let emojis = emojis
         .into_iter()
         .filter_map(|(emoji, file)| file.map(|file| (emoji, file)))
@@ -171,12 +350,12 @@ async fn post_post(
         .collect::<Vec<_>>();
     if !emojis.is_empty() {
         post_emoji::Entity::insert_many(emojis)
-            .exec(&tx)
+            .exec(db)
             .await
             .context_internal_server_error("failed to insert to database")?;
     }
 
-    let mentions = req
+    let mentions = contents
         .mentions
         .iter()
         .map(|mention| mention::ActiveModel {
@@ -187,49 +366,64 @@ async fn post_post(
         .collect::<Vec<_>>();
     if !mentions.is_empty() {
         mention::Entity::insert_many(mentions)
-            .exec(&tx)
+            .exec(db)
             .await
             .context_internal_server_error("failed to insert to database")?;
     }
 
-    let hashtags = req
+    let hashtags = contents
         .hashtags
-        .into_iter()
+        .iter()
         .map(|hashtag| hashtag::ActiveModel {
             post_id: ActiveValue::Set(post.id),
-            name: ActiveValue::Set(hashtag),
+            name: ActiveValue::Set(hashtag.clone()),
         })
         .collect::<Vec<_>>();
     if !hashtags.is_empty() {
         hashtag::Entity::insert_many(hashtags)
-            .exec(&tx)
+            .exec(db)
             .await
             .context_internal_server_error("failed to insert to database")?;
     }
 
-    tx.commit()
-        .await
-        .context_internal_server_error("failed to commmit database transaction")?;
-
-    let post_id = post.id.into();
-    let visibility = post.visibility.clone();
-
-    let post = post.into_json(&data).await?;
+    Ok(())
+}
 
-    let inboxes = match visibility {
-        sea_orm_active_enums::Visibility::Public
-        | sea_orm_active_enums::Visibility::Home
-        | sea_orm_active_enums::Visibility::Followers => get_follower_inboxes(&*data.db).await?,
-        sea_orm_active_enums::Visibility::DirectMessage => req
-            .mentions
-            .into_iter()
-            .map(|mention| mention.user_uri)
-            .collect::<Vec<_>>(),
+async fn create_poll(
+    post: &post::Model,
+    poll: CreatePoll,
+    db: &impl ConnectionTrait,
+) -> Result<()> {
+    let poll_activemodel = poll::ActiveModel {
+        post_id: ActiveValue::Set(post.id),
+        end_time: ActiveValue::Set(poll.end_time),
+        multiple: ActiveValue::Set(poll.multiple),
+        voters_count: ActiveValue::Set(Some(0)),
+        end_notified: ActiveValue::Set(false),
+        results_changed: ActiveValue::Set(false),
     };
+    poll_activemodel
+        .insert(db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
 
-    post.send(&data, inboxes).await?;
+    let options = poll
+        .options
+        .into_iter()
+        .enumerate()
+        .map(|(idx, name)| poll_option::ActiveModel {
+            post_id: ActiveValue::Set(post.id),
+            order: ActiveValue::Set(idx as i16),
+            name: ActiveValue::Set(name),
+            votes_count: ActiveValue::Set(0),
+        })
+        .collect::<Vec<_>>();
+    poll_option::Entity::insert_many(options)
+        .exec(db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
 
-    Ok(Json(IdResponse { id: post_id }))
+    Ok(())
 }
 
 #[utoipa::path(
@@ -259,6 +453,127 @@ async fn get_post(
     Ok(Json(Post::from_model(post, &*data.db).await?))
 }
 
+#[utoipa::path(
+    patch,
+    path = "/api/post/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    request_body = UpdatePost,
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access, req))]
+async fn patch_post(
+    data: Data<State>,
+    _access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+    Json(req): Json<UpdatePost>,
+) -> Result<()> {
+    let contents = resolve_post_contents(
+        &req.text,
+        req.source_content.as_deref(),
+        req.mentions,
+        req.emojis,
+        req.hashtags,
+        &data,
+    )
+    .await?;
+    let (text, source_media_type) = render_post_text(
+        req.text,
+        req.source_content.as_deref(),
+        req.source_media_type,
+        &contents,
+    )?;
+
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let existing = post::Entity::find_by_id(id)
+        .filter(post::Column::UserId.is_null())
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+    if existing.repost_id.is_some() && existing.text.is_empty() {
+        return Err(format_err!(BAD_REQUEST, "repost cannot be edited"));
+    }
+    if existing.repost_id.is_some() && text.is_empty() {
+        return Err(format_err!(BAD_REQUEST, "quote cannot have empty text"));
+    }
+
+    existing.save_revision(&tx).await?;
+
+    let mut post_activemodel: post::ActiveModel = existing.into();
+    post_activemodel.text = ActiveValue::Set(text);
+    post_activemodel.title = ActiveValue::Set(req.title);
+    post_activemodel.source_content = ActiveValue::Set(req.source_content);
+    post_activemodel.source_media_type = ActiveValue::Set(source_media_type);
+    post_activemodel.is_sensitive = ActiveValue::Set(req.is_sensitive);
+    post_activemodel.updated_at = ActiveValue::Set(Some(Utc::now().fixed_offset()));
+    let post = post_activemodel
+        .update(&tx)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    local_file::Model::detach_all_from_post(id, &tx).await?;
+    post_emoji::Entity::delete_many()
+        .filter(post_emoji::Column::PostId.eq(post.id))
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    mention::Entity::delete_many()
+        .filter(mention::Column::PostId.eq(post.id))
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+    hashtag::Entity::delete_many()
+        .filter(hashtag::Column::PostId.eq(post.id))
+        .exec(&tx)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    attach_post_contents(&post, req.files, &contents, &tx).await?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    let visibility = post.visibility.clone();
+    let note = match post.into_json(&data).await? {
+        NoteOrAnnounce::Note(note) => note,
+        NoteOrAnnounce::Announce(_) => {
+            return Err(format_err!(
+                INTERNAL_SERVER_ERROR,
+                "edited post is not a note"
+            ))
+        }
+    };
+
+    let inboxes = get_post_inboxes(
+        &visibility,
+        contents
+            .mentions
+            .into_iter()
+            .map(|mention| mention.user_uri)
+            .collect(),
+        &*data.db,
+    )
+    .await?;
+
+    let update = UpdateNote::new(*note)?;
+    update.send(&data, inboxes).await?;
+
+    Ok(())
+}
+
 #[utoipa::path(
     delete,
     path = "/api/post/{id}",
@@ -306,6 +621,25 @@ async fn delete_post(
             .collect::<Vec<_>>();
         let uri = existing.uri.clone();
 
+        // Pure reposts are retracted with Undo(Announce) rather than Delete
+        let announce = if was_mine && existing.repost_id.is_some() && existing.text.is_empty() {
+            match existing.clone().into_json(&data).await? {
+                NoteOrAnnounce::Announce(announce) => Some(announce),
+                NoteOrAnnounce::Note(_) => None,
+            }
+        } else {
+            None
+        };
+
+        // Followers keep the post in the featured collection until it is removed from there
+        if was_mine && existing.pinned_at.is_some() {
+            let remove = Remove::new_unpin(
+                uri.parse()
+                    .context_internal_server_error("malformed post URI")?,
+            )?;
+            remove.send(&data).await?;
+        }
+
         ModelTrait::delete(existing, &tx)
             .await
             .context_internal_server_error("failed to delete from database")?;
@@ -315,20 +649,18 @@ async fn delete_post(
             .context_internal_server_error("failed to commit database transaction")?;
 
         if was_mine {
-            let inboxes = match visibility {
-                sea_orm_active_enums::Visibility::Public
-                | sea_orm_active_enums::Visibility::Home
-                | sea_orm_active_enums::Visibility::Followers => {
-                    get_follower_inboxes(&*data.db).await?
-                }
-                sea_orm_active_enums::Visibility::DirectMessage => mention_user_uris,
-            };
+            let inboxes = get_post_inboxes(&visibility, mention_user_uris, &*data.db).await?;
 
-            let delete = Delete::new(
-                uri.parse()
-                    .context_internal_server_error("malformed post URI")?,
-            )?;
-            delete.send(&data, inboxes).await?;
+            if let Some(announce) = announce {
+                let undo = Undo::<Announce>::new(announce)?;
+                undo.send(&data, inboxes).await?;
+            } else {
+                let delete = Delete::new(
+                    uri.parse()
+                        .context_internal_server_error("malformed post URI")?,
+                )?;
+                delete.send(&data, inboxes).await?;
+            }
         }
 
         Ok(())
@@ -510,16 +842,6 @@ async fn delete_post_reaction(
         .context_internal_server_error("failed to query database")?;
 
     if let Some(existing) = existing {
-        let inbox = existing
-            .find_related(post::Entity)
-            .inner_join(user::Entity)
-            .select_only()
-            .column(user::Column::Inbox)
-            .into_tuple::<String>()
-            .one(&*data.db)
-            .await
-            .context_internal_server_error("failed to query database")?
-            .context_internal_server_error("user not found")?;
         let like = existing.clone().into_json(&data).await?;
 
         ModelTrait::delete(existing, &tx)
@@ -530,9 +852,243 @@ async fn delete_post_reaction(
             .await
             .context_internal_server_error("failed to commit database transaction")?;
 
-        let inbox = Url::parse(&inbox).context_internal_server_error("malformed user inbox URL")?;
-        let undo = Undo::<Like>::new(like)?;
-        undo.send(&data, vec![inbox]).await?;
+        like.send_undo(&data).await?;
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/post/{id}/pin",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn post_post_pin(
+    data: Data<State>,
+    _access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let existing = post::Entity::find_by_id(id)
+        .filter(post::Column::UserId.is_null())
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+    if existing.pinned_at.is_some() {
+        return Ok(());
+    }
+    if existing.repost_id.is_some() && existing.text.is_empty() {
+        return Err(format_err!(BAD_REQUEST, "repost cannot be pinned"));
+    }
+    if !existing.visibility.is_visible() {
+        return Err(format_err!(
+            BAD_REQUEST,
+            "only public or home posts can be pinned"
+        ));
+    }
+
+    let pinned_count = post::Entity::find()
+        .filter(post::Column::UserId.is_null())
+        .filter(post::Column::PinnedAt.is_not_null())
+        .count(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if pinned_count >= MAX_PINNED_POSTS {
+        return Err(format_err!(BAD_REQUEST, "too many pinned posts"));
+    }
+
+    let uri = existing.uri.clone();
+    let mut post_activemodel: post::ActiveModel = existing.into();
+    post_activemodel.pinned_at = ActiveValue::Set(Some(Utc::now().fixed_offset()));
+    post_activemodel
+        .update(&tx)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    let add = Add::new_pin(
+        uri.parse()
+            .context_internal_server_error("malformed post URI")?,
+    )?;
+    add.send(&data).await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/post/{id}/pin",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn delete_post_pin(
+    data: Data<State>,
+    _access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    let existing = post::Entity::find_by_id(id)
+        .filter(post::Column::UserId.is_null())
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+    if existing.pinned_at.is_none() {
+        return Ok(());
+    }
+
+    let uri = existing.uri.clone();
+    let mut post_activemodel: post::ActiveModel = existing.into();
+    post_activemodel.pinned_at = ActiveValue::Set(None);
+    post_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    let remove = Remove::new_unpin(
+        uri.parse()
+            .context_internal_server_error("malformed post URI")?,
+    )?;
+    remove.send(&data).await?;
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/post/{id}/vote",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    request_body = CreateVote,
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn post_post_vote(
+    data: Data<State>,
+    _access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+    Json(req): Json<CreateVote>,
+) -> Result<()> {
+    let tx = data
+        .db
+        .begin()
+        .await
+        .context_internal_server_error("failed to begin database transaction")?;
+
+    let (post, user) = post::Entity::find_by_id(id)
+        .find_also_related(user::Entity)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("post not found")?;
+    let user = user.context_bad_request("cannot vote on local poll")?;
+    let poll = post
+        .find_related(poll::Entity)
+        .one(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("poll not found")?;
+    if poll.is_ended() {
+        return Err(format_err!(BAD_REQUEST, "poll has ended"));
+    }
+
+    let mut choices = req
+        .choices
+        .into_iter()
+        .map(|choice| choice as i16)
+        .collect::<Vec<_>>();
+    choices.sort_unstable();
+    choices.dedup();
+    if choices.is_empty() {
+        return Err(format_err!(BAD_REQUEST, "no choice given"));
+    }
+    if !poll.multiple && choices.len() > 1 {
+        return Err(format_err!(BAD_REQUEST, "poll allows only one choice"));
+    }
+
+    let voted_count = poll
+        .find_related(poll_vote::Entity)
+        .filter(poll_vote::Column::UserId.is_null())
+        .count(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if voted_count > 0 {
+        return Err(format_err!(CONFLICT, "already voted poll"));
+    }
+
+    let options = poll
+        .find_related(poll_option::Entity)
+        .filter(poll_option::Column::Order.is_in(choices.clone()))
+        .order_by_asc(poll_option::Column::Order)
+        .all(&tx)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if options.len() != choices.len() {
+        return Err(format_err!(BAD_REQUEST, "poll option not found"));
+    }
+
+    let post_uri = Url::parse(&post.uri).context_internal_server_error("malformed post URI")?;
+    let user_uri = Url::parse(&user.uri).context_internal_server_error("malformed user URI")?;
+    let inbox =
+        Url::parse(&user.inbox).context_internal_server_error("malformed user inbox URL")?;
+    let mut votes = Vec::new();
+    for (idx, option) in options.into_iter().enumerate() {
+        let uri = generate_object_id()?;
+        poll.insert_vote(option.order, None, &uri, idx == 0, &tx)
+            .await?;
+        let vote = Note::new_vote(uri, option.name, post_uri.clone(), user_uri.clone());
+
+        // The vote is not a post, so it is served from the activities to be dereferenceable
+        activity::Model::record(
+            &WithContext::new_default(&vote),
+            vote.id.inner(),
+            Some(&post_uri),
+            std::slice::from_ref(&inbox),
+            &tx,
+        )
+        .await?;
+        votes.push(vote);
+    }
+
+    tx.commit()
+        .await
+        .context_internal_server_error("failed to commit database transaction")?;
+
+    // Each chosen option is sent as a separate note, as Mastodon does
+    for vote in votes {
+        let create = CreateNote::new(vote)?;
+        create.send(&data, vec![inbox.clone()]).await?;
     }
 
     Ok(())
@@ -0,0 +1,139 @@
+use activitypub_federation::config::Data;
+use axum::{extract, routing, Json, Router};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, ModelTrait, PaginatorTrait,
+    QueryFilter, QueryOrder,
+};
+use ulid::Ulid;
+use url::Url;
+
+use crate::{
+    ap::{follow::Follow, undo::Undo},
+    dto::{CreateRelay, Relay},
+    entity::relay,
+    error::{Context, Result},
+    format_err,
+    state::State,
+};
+
+use super::auth::Access;
+
+pub(super) fn create_router() -> Router {
+    Router::new()
+        .route("/", routing::get(get_relays).post(post_relay))
+        .route("/:id", routing::delete(delete_relay))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/relay",
+    responses(
+        (status = 200, body = Vec<Relay>),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn get_relays(data: Data<State>, _access: Access) -> Result<Json<Vec<Relay>>> {
+    let relays = relay::Entity::find()
+        .order_by_desc(relay::Column::CreatedAt)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    let relays = relays
+        .into_iter()
+        .map(Relay::from_model)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Json(relays))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/relay",
+    request_body = CreateRelay,
+    responses(
+        (status = 200, body = Relay),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn post_relay(
+    data: Data<State>,
+    _access: Access,
+    Json(req): Json<CreateRelay>,
+) -> Result<Json<Relay>> {
+    let existing_count = relay::Entity::find()
+        .filter(relay::Column::Inbox.eq(req.inbox.as_str()))
+        .count(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    if existing_count > 0 {
+        return Err(format_err!(CONFLICT, "relay already exists"));
+    }
+
+    let id = Ulid::new();
+    let relay_activemodel = relay::ActiveModel {
+        id: ActiveValue::Set(id.into()),
+        inbox: ActiveValue::Set(req.inbox.to_string()),
+        actor: ActiveValue::Set(None),
+        accepted: ActiveValue::Set(false),
+        created_at: ActiveValue::Set(Utc::now().fixed_offset()),
+    };
+    let relay = relay_activemodel
+        .insert(&*data.db)
+        .await
+        .context_internal_server_error("failed to insert to database")?;
+
+    let follow = Follow::new_relay(id)?;
+    if let Err(error) = follow.send_relay(&data, req.inbox).await {
+        // The relay is removed so that it can be added again
+        relay::Entity::delete_by_id(id)
+            .exec(&*data.db)
+            .await
+            .context_internal_server_error("failed to delete from database")?;
+        return Err(error);
+    }
+
+    Ok(Json(Relay::from_model(relay)?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/relay/{id}",
+    params(
+        ("id" = String, format = "ulid"),
+    ),
+    responses(
+        (status = 200),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn delete_relay(
+    data: Data<State>,
+    _access: Access,
+    extract::Path(id): extract::Path<Ulid>,
+) -> Result<()> {
+    let relay = relay::Entity::find_by_id(id)
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+        .context_not_found("relay not found")?;
+    let inbox =
+        Url::parse(&relay.inbox).context_internal_server_error("malformed relay inbox URL")?;
+
+    ModelTrait::delete(relay, &*data.db)
+        .await
+        .context_internal_server_error("failed to delete from database")?;
+
+    let undo = Undo::<Follow>::new(Follow::new_relay(id)?)?;
+    undo.send(&data, vec![inbox]).await?;
+
+    Ok(())
+}
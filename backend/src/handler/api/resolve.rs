@@ -134,7 +134,7 @@ async fn get_resolve_link(
     let object = object.inner().clone();
     let dto = match object {
         ApObject::Note(note) => {
-            let model = post::Model::from_json(NoteOrAnnounce::Note(*note), &data).await?;
+            let model = post::Model::from_json(NoteOrAnnounce::Note(note), &data).await?;
             dto::Object::Post(Box::new(dto::Post::from_model(model, &*data.db).await?))
         }
         ApObject::Person(person) => {
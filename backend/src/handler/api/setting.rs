@@ -1,14 +1,18 @@
-use activitypub_federation::config::Data;
+use activitypub_federation::{config::Data, fetch::object_id::ObjectId};
 use axum::{routing, Json, Router};
 use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait, PaginatorTrait, TransactionTrait};
 use serde::Deserialize;
 use ulid::Ulid;
+use url::Url;
 use utoipa::ToSchema;
 
 use crate::{
-    ap::person::PersonUpdate,
+    ap::{
+        person::{LocalPerson, PersonUpdate},
+        r#move::Move,
+    },
     dto::Setting,
-    entity::{local_file, setting},
+    entity::{local_file, setting, user},
     error::{Context, Result},
     format_err,
     state::State,
@@ -20,6 +24,7 @@ pub(super) fn create_router() -> Router {
     Router::new()
         .route("/", routing::get(get_setting).put(put_setting))
         .route("/initial", routing::post(post_initial_setting))
+        .route("/move", routing::post(post_move))
 }
 
 #[utoipa::path(
@@ -56,6 +61,13 @@ pub struct PutSettingReq {
     pub maintainer_email: Option<String>,
     #[serde(default)]
     pub theme_color: Option<String>,
+    #[serde(default)]
+    pub hide_follows: Option<bool>,
+    #[serde(default)]
+    pub manually_approves_followers: Option<bool>,
+    #[schema(value_type = Option<Vec<String>>, format = "url")]
+    #[serde(default)]
+    pub also_known_as: Option<Vec<Url>>,
 }
 
 #[utoipa::path(
@@ -102,6 +114,21 @@ async fn put_setting(
     if let Some(v) = req.theme_color {
         setting_activemodel.theme_color = ActiveValue::Set(Some(v));
     }
+    if let Some(v) = req.hide_follows {
+        setting_activemodel.hide_follows = ActiveValue::Set(v);
+    }
+    if let Some(v) = req.manually_approves_followers {
+        setting_activemodel.manually_approves_followers = ActiveValue::Set(v);
+    }
+    if let Some(v) = req.also_known_as {
+        let mut also_known_as = Vec::with_capacity(v.len());
+        for alias in v {
+            let alias: ObjectId<user::Model> = alias.into();
+            let alias = alias.dereference(&data).await?;
+            also_known_as.push(alias.uri);
+        }
+        setting_activemodel.user_also_known_as = ActiveValue::Set(also_known_as.into());
+    }
 
     let tx = data
         .db
@@ -143,6 +170,57 @@ async fn put_setting(
     Ok(Json(Setting::from_model(setting)))
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PostMoveReq {
+    #[schema(value_type = String, format = "url")]
+    pub target: Url,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/setting/move",
+    request_body = PostMoveReq,
+    responses(
+        (status = 200, body = Setting),
+    ),
+    security(
+        ("access_key" = []),
+    ),
+)]
+#[tracing::instrument(skip(data, _access))]
+async fn post_move(
+    data: Data<State>,
+    _access: Access,
+    Json(req): Json<PostMoveReq>,
+) -> Result<Json<Setting>> {
+    let target: ObjectId<user::Model> = req.target.into();
+    let target = target.dereference_forced(&data).await?;
+    if !target.also_known_as().contains(&LocalPerson::id()) {
+        return Err(format_err!(
+            BAD_REQUEST,
+            "move target does not list this account as its alias"
+        ));
+    }
+    let target_uri = Url::parse(&target.uri).context_internal_server_error("malformed user URI")?;
+
+    let setting = setting::Model::get(&*data.db).await?;
+    let mut setting_activemodel: setting::ActiveModel = setting.into();
+    setting_activemodel.user_moved_to = ActiveValue::Set(Some(target_uri.to_string()));
+    let setting = setting_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    let update = PersonUpdate::new_self(&data).await?;
+    update.send(&data).await?;
+
+    let r#move = Move::new(target_uri)?;
+    r#move.send(&data).await?;
+
+    Ok(Json(Setting::from_model(setting)))
+}
+
 #[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PostInitialSettingReq {
@@ -1,6 +1,6 @@
 use activitypub_federation::config::Data;
 use askama::Template;
-use axum::{http::StatusCode, response::IntoResponse};
+use axum::{extract, http::StatusCode, response::IntoResponse};
 use sea_orm::{ConnectionTrait, EntityTrait, QuerySelect};
 use ulid::Ulid;
 
@@ -137,3 +137,19 @@ pub async fn get_not_found(data: Data<State>) -> Result<RespOrFrontend<()>> {
     )
     .await
 }
+
+pub async fn get_tag(
+    data: Data<State>,
+    extract::Path(name): extract::Path<String>,
+) -> Result<RespOrFrontend<()>> {
+    let title = format!("#{}", name);
+    let ctx = FrontendContext {
+        title: Some(title.clone()),
+        description: None,
+        og_type: Some("website".to_string()),
+        og_title: Some(title),
+        og_description: None,
+        og_image: None,
+    };
+    RespOrFrontend::frontend(StatusCode::OK, &*data.db, ctx).await
+}
@@ -0,0 +1,341 @@
+use std::time::Duration;
+
+use activitypub_federation::{
+    config::{Data, FederationConfig},
+    fetch::object_id::ObjectId,
+    traits::Object,
+};
+use chrono::{Duration as ChronoDuration, Utc};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait,
+    EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use url::Url;
+
+use crate::{
+    ap::{note::UpdateNote, NoteOrAnnounce},
+    entity::{
+        delivery, instance, mention, poll, poll_vote, post, post_revision, report, setting, user,
+    },
+    error::{Context, Error},
+    queue::{Event, Notification, NotificationType, Update},
+    sanitize::sanitize_html,
+    state::State,
+    util::get_post_inboxes,
+};
+
+const JOB_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Remote users not fetched for this long are refreshed.
+const USER_REFRESH_AGE_HOURS: i64 = 24;
+
+/// Maximum number of remote users refreshed per run.
+const USER_REFRESH_BATCH_SIZE: u64 = 10;
+
+/// Dead instances are probed again after this long.
+const INSTANCE_PROBE_INTERVAL_HOURS: i64 = 6;
+
+/// Maximum number of dead instances probed per run.
+const INSTANCE_PROBE_BATCH_SIZE: u64 = 10;
+
+/// Runs the periodic background jobs until the server is stopped.
+pub async fn run(federation_config: FederationConfig<State>) {
+    let stopper = federation_config.to_request_data().stopper.clone();
+    if let Err(error) = sanitize_stored_html(&federation_config.to_request_data()).await {
+        tracing::warn!("failed to sanitize stored HTML\n{:?}", error);
+    }
+
+    let mut interval = tokio::time::interval(JOB_INTERVAL);
+    while stopper.stop_future(interval.tick()).await.is_some() {
+        // Request data counts the fetches made with it, so it is renewed for every run
+        let data = federation_config.to_request_data();
+        if let Err(error) = notify_ended_polls(&data).await {
+            tracing::warn!("failed to notify ended polls\n{:?}", error);
+        }
+        if let Err(error) = federate_changed_polls(&data).await {
+            tracing::warn!("failed to federate changed polls\n{:?}", error);
+        }
+        if let Err(error) = refresh_stale_users(&data).await {
+            tracing::warn!("failed to refresh stale users\n{:?}", error);
+        }
+        if let Err(error) = probe_dead_instances(&data).await {
+            tracing::warn!("failed to probe dead instances\n{:?}", error);
+        }
+    }
+}
+
+/// Sanitizes the remote HTML stored before it was sanitized on receipt, if the migration flagged
+/// it as pending.
+#[tracing::instrument(skip(data))]
+async fn sanitize_stored_html(data: &Data<State>) -> Result<(), Error> {
+    let Some(setting) = setting::Entity::find()
+        .one(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?
+    else {
+        return Ok(());
+    };
+    if !setting.remote_html_sanitize_pending {
+        return Ok(());
+    }
+
+    tracing::info!("sanitizing stored remote HTML...");
+
+    let posts = post::Entity::find()
+        .filter(post::Column::UserId.is_not_null())
+        .select_only()
+        .columns([post::Column::Id, post::Column::Text])
+        .into_tuple::<(uuid::Uuid, String)>()
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    sanitize_column::<post::Entity>(posts, post::Column::Id, post::Column::Text, &*data.db).await?;
+
+    let revisions = post_revision::Entity::find()
+        .inner_join(post::Entity)
+        .filter(post::Column::UserId.is_not_null())
+        .select_only()
+        .columns([post_revision::Column::Id, post_revision::Column::Text])
+        .into_tuple::<(uuid::Uuid, String)>()
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    sanitize_column::<post_revision::Entity>(
+        revisions,
+        post_revision::Column::Id,
+        post_revision::Column::Text,
+        &*data.db,
+    )
+    .await?;
+
+    let users = user::Entity::find()
+        .filter(user::Column::Description.is_not_null())
+        .select_only()
+        .columns([user::Column::Id, user::Column::Description])
+        .into_tuple::<(uuid::Uuid, String)>()
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    sanitize_column::<user::Entity>(
+        users,
+        user::Column::Id,
+        user::Column::Description,
+        &*data.db,
+    )
+    .await?;
+
+    let reports = report::Entity::find()
+        .select_only()
+        .columns([report::Column::Id, report::Column::Content])
+        .into_tuple::<(uuid::Uuid, String)>()
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+    sanitize_column::<report::Entity>(
+        reports,
+        report::Column::Id,
+        report::Column::Content,
+        &*data.db,
+    )
+    .await?;
+
+    let setting_activemodel = setting::ActiveModel {
+        id: ActiveValue::Unchanged(setting.id),
+        remote_html_sanitize_pending: ActiveValue::Set(false),
+        ..Default::default()
+    };
+    setting_activemodel
+        .update(&*data.db)
+        .await
+        .context_internal_server_error("failed to update database")?;
+
+    Ok(())
+}
+
+/// Updates `column` of the rows whose HTML changes when sanitized.
+async fn sanitize_column<E>(
+    rows: Vec<(uuid::Uuid, String)>,
+    id_column: E::Column,
+    column: E::Column,
+    db: &impl ConnectionTrait,
+) -> Result<(), Error>
+where
+    E: EntityTrait,
+{
+    for (id, html) in rows {
+        let sanitized = sanitize_html(&html);
+        if sanitized != html {
+            E::update_many()
+                .col_expr(column, Expr::value(sanitized))
+                .filter(id_column.eq(id))
+                .exec(db)
+                .await
+                .context_internal_server_error("failed to update database")?;
+        }
+    }
+    Ok(())
+}
+
+/// Refetches the remote users that have not been fetched recently, so that rotated keys and
+/// profile changes are picked up even if nobody dereferences them.
+#[tracing::instrument(skip(data))]
+async fn refresh_stale_users(data: &Data<State>) -> Result<(), Error> {
+    let stale_before = Utc::now() - ChronoDuration::hours(USER_REFRESH_AGE_HOURS);
+    let users = user::Entity::find()
+        .filter(user::Column::GoneAt.is_null())
+        .filter(user::Column::LastFetchedAt.lt(stale_before.fixed_offset()))
+        .order_by_asc(user::Column::LastFetchedAt)
+        .limit(USER_REFRESH_BATCH_SIZE)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    for user in users {
+        if let Err(error) = user.refresh(data).await {
+            tracing::warn!("failed to refresh user {}\n{:?}", user.uri, error);
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether the dead instances are back, so that deliveries to them can resume.
+#[tracing::instrument(skip(data))]
+async fn probe_dead_instances(data: &Data<State>) -> Result<(), Error> {
+    let probed_before = Utc::now() - ChronoDuration::hours(INSTANCE_PROBE_INTERVAL_HOURS);
+    let instances = instance::Entity::find()
+        .filter(instance::Column::DeadSince.is_not_null())
+        .filter(
+            Condition::any()
+                .add(instance::Column::LastProbedAt.is_null())
+                .add(instance::Column::LastProbedAt.lt(probed_before.fixed_offset())),
+        )
+        .order_by_asc(instance::Column::LastProbedAt)
+        .limit(INSTANCE_PROBE_BATCH_SIZE)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    for instance in instances {
+        let is_alive = data
+            .http_client
+            .get(format!("https://{}/.well-known/nodeinfo", instance.host))
+            .send()
+            .await
+            .is_ok_and(|resp| resp.status().is_success());
+        instance.record_probe(is_alive, &*data.db).await?;
+        if is_alive {
+            tracing::info!("instance {} is back", instance.host);
+            delivery::Model::resume_host(&instance.host, &*data.db).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Notifies the polls that ended since the last run, if they are local or voted by the local
+/// person.
+#[tracing::instrument(skip(data))]
+async fn notify_ended_polls(data: &Data<State>) -> Result<(), Error> {
+    let polls = poll::Entity::find()
+        .filter(poll::Column::EndNotified.eq(false))
+        .filter(poll::Column::EndTime.lte(Utc::now().fixed_offset()))
+        .find_also_related(post::Entity)
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    for (poll, post) in polls {
+        let post = post.context_internal_server_error("failed to find poll post")?;
+
+        let voted_count = poll
+            .find_related(poll_vote::Entity)
+            .filter(poll_vote::Column::UserId.is_null())
+            .count(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?;
+        let is_mine = post.user_id.is_none();
+
+        if !is_mine && voted_count > 0 {
+            // Refetch the remote poll to get the final results
+            let post_uri: ObjectId<post::Model> = post
+                .uri
+                .parse::<Url>()
+                .context_internal_server_error("malformed post URI")?
+                .into();
+            if let Err(error) = post_uri.dereference_forced(data).await {
+                tracing::warn!("failed to refetch ended poll {}\n{:?}", post.uri, error);
+            } else {
+                let event = Event::Update(Update::UpdatePost {
+                    post_id: post.id.into(),
+                });
+                event.send(&*data.db).await?;
+            }
+        }
+
+        let poll_activemodel = poll::ActiveModel {
+            post_id: ActiveValue::Unchanged(poll.post_id),
+            end_notified: ActiveValue::Set(true),
+            ..Default::default()
+        };
+        poll_activemodel
+            .update(&*data.db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+
+        if is_mine || voted_count > 0 {
+            let event = Event::Notification(Notification::new(NotificationType::PollEnded {
+                post_id: post.id.into(),
+            }));
+            event.send(&*data.db).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends the updated results of the local polls that received votes since the last run.
+#[tracing::instrument(skip(data))]
+async fn federate_changed_polls(data: &Data<State>) -> Result<(), Error> {
+    let polls = poll::Entity::find()
+        .filter(poll::Column::ResultsChanged.eq(true))
+        .find_also_related(post::Entity)
+        .filter(post::Column::UserId.is_null())
+        .all(&*data.db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    for (poll, post) in polls {
+        let post = post.context_internal_server_error("failed to find poll post")?;
+
+        let poll_activemodel = poll::ActiveModel {
+            post_id: ActiveValue::Unchanged(poll.post_id),
+            results_changed: ActiveValue::Set(false),
+            ..Default::default()
+        };
+        poll_activemodel
+            .update(&*data.db)
+            .await
+            .context_internal_server_error("failed to update database")?;
+
+        let mention_user_uris = post
+            .find_related(mention::Entity)
+            .select_only()
+            .column(mention::Column::UserUri)
+            .into_tuple::<String>()
+            .all(&*data.db)
+            .await
+            .context_internal_server_error("failed to query database")?
+            .into_iter()
+            .filter_map(|uri| Url::parse(&uri).ok())
+            .collect::<Vec<_>>();
+        let inboxes = get_post_inboxes(&post.visibility, mention_user_uris, &*data.db).await?;
+
+        if let NoteOrAnnounce::Note(note) = post.into_json(data).await? {
+            let update = UpdateNote::new(*note)?;
+            update.send(data, inboxes).await?;
+        }
+    }
+
+    Ok(())
+}
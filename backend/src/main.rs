@@ -10,14 +10,18 @@ use tracing_subscriber::{prelude::__tracing_subscriber_SubscriberExt, util::Subs
 
 mod ap;
 mod config;
+mod delivery;
 mod dto;
 mod entity;
 mod entity_impl;
 mod error;
 mod fmt;
 mod handler;
+mod job;
+mod mfm;
 mod object_store;
 mod queue;
+mod sanitize;
 mod state;
 mod util;
 
@@ -78,14 +82,29 @@ async fn main() -> anyhow::Result<()> {
     let state = crate::state::State::new(db, stopper.clone())
         .await
         .context("failed to construct app state")?;
-    let federation_config = FederationConfig::builder()
+    let mut federation_config = FederationConfig::builder();
+    federation_config
         .domain(&crate::config::CONFIG.public_domain)
         .app_data(state.clone())
         .debug(crate::config::CONFIG.debug)
+        .url_verifier(Box::new(
+            crate::ap::url_verifier::BlockedInstanceVerifier::new(state.db.clone()),
+        ));
+    if crate::config::CONFIG.secure_mode {
+        if let Ok(local_person) = crate::ap::person::LocalPerson::get(&*state.db).await {
+            federation_config.signed_fetch_actor(&local_person);
+        } else {
+            tracing::warn!("Instance is not initialized yet, outgoing fetches will not be signed");
+        }
+    }
+    let federation_config = federation_config
         .build()
         .await
         .context("failed to build federation config")?;
 
+    tokio::spawn(crate::job::run(federation_config.clone()));
+    tokio::spawn(crate::delivery::run(federation_config.clone()));
+
     let router = crate::handler::create_router(federation_config)
         .await
         .context("failed to create router")?;
@@ -0,0 +1,373 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use once_cell::sync::Lazy;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+use regex::Regex;
+use url::Url;
+
+use crate::{config::CONFIG, error::Result, format_err, sanitize::sanitize_html};
+
+/// Media type of Misskey-flavored Markdown.
+pub const MFM_MEDIA_TYPE: &str = "text/x.misskeymarkdown";
+
+/// Media type of CommonMark.
+pub const MARKDOWN_MEDIA_TYPE: &str = "text/markdown";
+
+static CODE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)```.*?```|`[^`\n]*`").unwrap());
+
+static LINK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\??\[[^\]\n]*\]\([^)\n]*\)|<https?://[^>\s]*>").unwrap());
+
+static MENTION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?:^|[^\w@/.])@([A-Za-z0-9_](?:[A-Za-z0-9_.-]*[A-Za-z0-9_])?)@([A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)+)",
+    )
+    .unwrap()
+});
+
+static HASHTAG_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|[^\w&/#])#([\p{L}\p{M}\p{N}_]+)").unwrap());
+
+static EMOJI_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r":([A-Za-z0-9_+-]+):").unwrap());
+
+/// Splits a line into the quote markers, the indentation and the rest.
+static LINE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^((?:[ \t]{0,3}>[ \t]?)*)([ \t]*)(.*)$").unwrap());
+
+/// Start of an ordered list item, which is escaped before the delimiter.
+static ORDERED_LIST_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\d{1,9})([.)](?:[ \t]|$))").unwrap());
+
+/// Headings, bullet list items, setext underlines and thematic breaks, which are escaped before
+/// the first character.
+static BLOCK_MARKER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:#{1,6}(?:[ \t]|$)|[-+*](?:[ \t]|$)|=+[ \t]*$|-+[ \t]*$|(?:[-*_][ \t]*){3,}$)")
+        .unwrap()
+});
+
+/// A `@handle@host` mention found in a text.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Mention {
+    pub handle: String,
+    pub host: String,
+}
+
+/// Mentions, hashtags and custom emoji codes found in a Misskey-flavored Markdown text.
+#[derive(Debug, Default)]
+pub struct Extracted {
+    pub mentions: Vec<Mention>,
+    pub hashtags: Vec<String>,
+    pub emojis: Vec<String>,
+}
+
+impl Extracted {
+    /// Extracts from the text, ignoring anything inside code and links.
+    pub fn from_text(text: &str) -> Self {
+        let mut this = Self::default();
+        this.extend(text);
+        this
+    }
+
+    /// Adds what is found in another text, skipping the duplicates.
+    pub fn extend(&mut self, text: &str) {
+        let text = CODE_REGEX.replace_all(text, " ");
+        let text = LINK_REGEX.replace_all(&text, " ");
+
+        for captures in MENTION_REGEX.captures_iter(&text) {
+            let mention = Mention {
+                handle: captures[1].to_string(),
+                host: captures[2].to_lowercase(),
+            };
+            if !self.mentions.contains(&mention) {
+                self.mentions.push(mention);
+            }
+        }
+
+        for captures in HASHTAG_REGEX.captures_iter(&text) {
+            let hashtag = &captures[1];
+            // Misskey does not treat numbers like `#1` as hashtags
+            if hashtag.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            if !self.hashtags.iter().any(|existing| existing == hashtag) {
+                self.hashtags.push(hashtag.to_string());
+            }
+        }
+
+        for captures in EMOJI_REGEX.captures_iter(&text) {
+            let emoji = &captures[1];
+            if !self.emojis.iter().any(|existing| existing == emoji) {
+                self.emojis.push(emoji.to_string());
+            }
+        }
+    }
+}
+
+/// Renders the source text to HTML for `Note.content`.
+///
+/// Line breaks are kept as they are in MFM, unlike in Markdown. Mentions are linked to the users
+/// in `mentions`, keyed by their `@handle@host` name, and hashtags to their pages.
+pub fn render_html(
+    source: &str,
+    media_type: &str,
+    mentions: &HashMap<String, Url>,
+) -> Result<String> {
+    let is_mfm = match media_type {
+        MFM_MEDIA_TYPE => true,
+        MARKDOWN_MEDIA_TYPE => false,
+        _ => return Err(format_err!(BAD_REQUEST, "unsupported source media type")),
+    };
+
+    let source = if is_mfm {
+        Cow::Owned(escape_mfm_blocks(source))
+    } else {
+        Cow::Borrowed(source)
+    };
+
+    let mut events = Vec::new();
+    let mut text = String::new();
+    // Text in code and links is not linked again
+    let mut verbatim_depth = 0;
+    for event in Parser::new_ext(&source, Options::ENABLE_STRIKETHROUGH) {
+        if let Event::Text(fragment) = &event {
+            // The parser may split a text into fragments, so they are joined to find the links
+            text.push_str(fragment);
+            continue;
+        }
+        if !text.is_empty() {
+            if verbatim_depth > 0 {
+                events.push(Event::Text(CowStr::from(std::mem::take(&mut text))));
+            } else {
+                events.extend(link_text(&std::mem::take(&mut text), mentions));
+            }
+        }
+
+        match event {
+            Event::Start(Tag::CodeBlock(_) | Tag::Link { .. }) => {
+                verbatim_depth += 1;
+                events.push(event);
+            }
+            Event::End(TagEnd::CodeBlock | TagEnd::Link) => {
+                verbatim_depth -= 1;
+                events.push(event);
+            }
+            Event::SoftBreak if is_mfm => events.push(Event::HardBreak),
+            event => events.push(event),
+        }
+    }
+    if !text.is_empty() {
+        events.extend(link_text(&text, mentions));
+    }
+
+    let mut html = String::new();
+    html::push_html(&mut html, events.into_iter());
+    Ok(sanitize_html(&html))
+}
+
+/// Escapes the CommonMark block constructs that MFM does not have, so that they are kept as
+/// text. Indentation is kept with no-break spaces instead of turning into a code block.
+fn escape_mfm_blocks(source: &str) -> String {
+    let mut in_code_block = false;
+    let mut lines = Vec::new();
+    for line in source.split('\n') {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            lines.push(line.to_string());
+            continue;
+        }
+        if in_code_block {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let captures = LINE_REGEX.captures(line).unwrap();
+        let (quote, indent, rest) = (&captures[1], &captures[2], &captures[3]);
+        let mut escaped = quote.to_string();
+        if !rest.is_empty() {
+            escaped.extend(indent.chars().map(|c| match c {
+                '\t' => "\u{a0}\u{a0}\u{a0}\u{a0}",
+                _ => "\u{a0}",
+            }));
+        }
+        if ORDERED_LIST_REGEX.is_match(rest) {
+            escaped.push_str(&ORDERED_LIST_REGEX.replace(rest, r"$1\$2"));
+        } else if BLOCK_MARKER_REGEX.is_match(rest) {
+            escaped.push('\\');
+            escaped.push_str(rest);
+        } else {
+            escaped.push_str(rest);
+        }
+        lines.push(escaped);
+    }
+    lines.join("\n")
+}
+
+/// Splits the text into plain text and links to the mentioned users and hashtags.
+fn link_text(text: &str, mentions: &HashMap<String, Url>) -> Vec<Event<'static>> {
+    let mut links = Vec::new();
+    for captures in MENTION_REGEX.captures_iter(text) {
+        let (handle, host) = (&captures[1], captures[2].to_lowercase());
+        if let Some(uri) = mentions.get(&format!("@{}@{}", handle, host)) {
+            let start = captures.get(1).unwrap().start() - 1;
+            let end = captures.get(2).unwrap().end();
+            let html = format!(
+                r#"<span class="h-card"><a href="{}" class="u-url mention">@<span>{}</span></a></span>"#,
+                escape_html(uri.as_str()),
+                escape_html(handle),
+            );
+            links.push((start, end, html));
+        }
+    }
+    for captures in HASHTAG_REGEX.captures_iter(text) {
+        let hashtag = captures.get(1).unwrap();
+        if hashtag.as_str().chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+        let html = format!(
+            r#"<a href="https://{}/tags/{}" class="mention hashtag" rel="tag">#<span>{}</span></a>"#,
+            CONFIG.public_domain,
+            escape_html(&url_escape(hashtag.as_str())),
+            escape_html(hashtag.as_str()),
+        );
+        links.push((hashtag.start() - 1, hashtag.end(), html));
+    }
+    links.sort_by_key(|(start, _, _)| *start);
+
+    let mut events = Vec::new();
+    let mut pos = 0;
+    for (start, end, html) in links {
+        if start < pos {
+            continue;
+        }
+        if start > pos {
+            events.push(Event::Text(CowStr::from(text[pos..start].to_string())));
+        }
+        events.push(Event::InlineHtml(CowStr::from(html)));
+        pos = end;
+    }
+    if pos < text.len() {
+        events.push(Event::Text(CowStr::from(text[pos..].to_string())));
+    }
+    events
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn url_escape(text: &str) -> String {
+    url::form_urlencoded::byte_serialize(text.as_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mention(handle: &str, host: &str) -> Mention {
+        Mention {
+            handle: handle.to_string(),
+            host: host.to_string(),
+        }
+    }
+
+    #[test]
+    fn remote_mention_is_extracted() {
+        let extracted = Extracted::from_text("hello @alice@Example.COM and @bob@social.example!");
+        assert_eq!(
+            extracted.mentions,
+            vec![
+                mention("alice", "example.com"),
+                mention("bob", "social.example"),
+            ]
+        );
+    }
+
+    #[test]
+    fn mention_without_host_is_ignored() {
+        let extracted = Extracted::from_text("hello @alice, and mail to alice@example.com");
+        assert!(extracted.mentions.is_empty());
+    }
+
+    #[test]
+    fn duplicate_mention_is_extracted_once() {
+        let extracted = Extracted::from_text("@alice@example.com @alice@example.com");
+        assert_eq!(extracted.mentions, vec![mention("alice", "example.com")]);
+    }
+
+    #[test]
+    fn hashtags_are_extracted() {
+        let extracted = Extracted::from_text("#rust and #러스트 but not #1 or a#b");
+        assert_eq!(extracted.hashtags, vec!["rust", "러스트"]);
+    }
+
+    #[test]
+    fn emojis_are_extracted() {
+        let extracted = Extracted::from_text(":blobcat: :blob_cat+1: :blobcat:");
+        assert_eq!(extracted.emojis, vec!["blobcat", "blob_cat+1"]);
+    }
+
+    #[test]
+    fn code_is_skipped() {
+        let extracted = Extracted::from_text(
+            "`@alice@example.com #inline`\n```\n@bob@example.com #block :emoji:\n```",
+        );
+        assert!(extracted.mentions.is_empty());
+        assert!(extracted.hashtags.is_empty());
+        assert!(extracted.emojis.is_empty());
+    }
+
+    #[test]
+    fn links_are_skipped() {
+        let extracted = Extracted::from_text(
+            "https://example.com/@alice@example.com https://example.com/page#section \
+             [@bob@example.com #label](https://example.com) <https://example.com/#tag>",
+        );
+        assert!(extracted.mentions.is_empty());
+        assert!(extracted.hashtags.is_empty());
+    }
+
+    fn render_mfm(source: &str) -> String {
+        render_html(source, MFM_MEDIA_TYPE, &HashMap::new()).unwrap()
+    }
+
+    #[test]
+    fn commonmark_blocks_stay_literal() {
+        assert_eq!(
+            render_mfm("# heading\n- item\n1. one"),
+            "<p># heading<br>\n- item<br>\n1. one</p>\n"
+        );
+    }
+
+    #[test]
+    fn quote_content_stays_literal() {
+        assert_eq!(
+            render_mfm("> # quoted\n> - item"),
+            "<blockquote>\n<p># quoted<br>\n- item</p>\n</blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn code_block_content_stays_literal() {
+        assert_eq!(
+            render_mfm("```\n# code #tag\n- item\n```"),
+            "<pre><code># code #tag\n- item\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn hashtags_link_to_their_page() {
+        // `CONFIG` fails to load without an object store type
+        std::env::set_var("OBJECT_STORE_TYPE", "local_filesystem");
+        assert_eq!(
+            render_mfm("hello #rust"),
+            format!(
+                r#"<p>hello <a href="https://{}/tags/rust" class="mention hashtag" rel="nofollow noopener noreferrer">#<span>rust</span></a></p>
+"#,
+                CONFIG.public_domain
+            )
+        );
+    }
+}
@@ -24,6 +24,11 @@ pub enum Update {
         post_id: Ulid,
     },
     #[serde(rename_all = "camelCase")]
+    UpdatePost {
+        #[schema(value_type = String, format = "ulid")]
+        post_id: Ulid,
+    },
+    #[serde(rename_all = "camelCase")]
     DeletePost {
         #[schema(value_type = String, format = "ulid")]
         post_id: Ulid,
@@ -74,6 +79,11 @@ pub enum NotificationType {
         user_id: Ulid,
     },
     #[serde(rename_all = "camelCase")]
+    FollowRequest {
+        #[schema(value_type = String, format = "ulid")]
+        user_id: Ulid,
+    },
+    #[serde(rename_all = "camelCase")]
     CreateReport {
         #[schema(value_type = String, format = "ulid")]
         report_id: Ulid,
@@ -84,6 +94,11 @@ pub enum NotificationType {
         post_id: Ulid,
     },
     #[serde(rename_all = "camelCase")]
+    Replied {
+        #[schema(value_type = String, format = "ulid")]
+        post_id: Ulid,
+    },
+    #[serde(rename_all = "camelCase")]
     Reposted {
         #[schema(value_type = String, format = "ulid")]
         user_id: Ulid,
@@ -102,6 +117,11 @@ pub enum NotificationType {
         #[schema(value_type = String, format = "ulid")]
         reaction_id: Ulid,
     },
+    #[serde(rename_all = "camelCase")]
+    PollEnded {
+        #[schema(value_type = String, format = "ulid")]
+        post_id: Ulid,
+    },
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
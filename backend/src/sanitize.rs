@@ -0,0 +1,153 @@
+use std::collections::{HashMap, HashSet};
+
+use ammonia::{Builder, UrlRelative};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Follows the allowlist of Mastodon, so that remote content renders the same as there.
+static HTML_SANITIZER: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut builder = Builder::empty();
+    builder
+        .tags(HashSet::from([
+            "p",
+            "br",
+            "span",
+            "a",
+            "del",
+            "pre",
+            "blockquote",
+            "code",
+            "b",
+            "strong",
+            "u",
+            "i",
+            "em",
+            "ul",
+            "ol",
+            "li",
+        ]))
+        .tag_attributes(HashMap::from([
+            ("a", HashSet::from(["href", "translate"])),
+            ("span", HashSet::from(["translate"])),
+            ("ol", HashSet::from(["start", "reversed"])),
+            ("li", HashSet::from(["value"])),
+        ]))
+        .allowed_classes(HashMap::from([
+            (
+                "a",
+                HashSet::from([
+                    "h-card",
+                    "u-url",
+                    "mention",
+                    "hashtag",
+                    "ellipsis",
+                    "invisible",
+                ]),
+            ),
+            (
+                "span",
+                HashSet::from([
+                    "h-card",
+                    "p-nickname",
+                    "mention",
+                    "hashtag",
+                    "ellipsis",
+                    "invisible",
+                    "quote-inline",
+                ]),
+            ),
+        ]))
+        .url_schemes(HashSet::from([
+            "http", "https", "dat", "dweb", "ipfs", "ipns", "ssb", "gopher", "xmpp", "magnet",
+            "gemini",
+        ]))
+        .url_relative(UrlRelative::Deny)
+        .link_rel(Some("nofollow noopener noreferrer"))
+        .clean_content_tags(HashSet::from(["script", "style"]));
+    builder
+});
+
+/// Strips every tag, keeping only the text.
+static TEXT_SANITIZER: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut builder = Builder::empty();
+    builder.clean_content_tags(HashSet::from(["script", "style"]));
+    builder
+});
+
+static LINE_BREAK_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<br\s*/?>").unwrap());
+
+static PARAGRAPH_END_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)</p\s*>").unwrap());
+
+/// Removes the tags, attributes and classes not in the allowlist.
+pub fn sanitize_html(html: &str) -> String {
+    HTML_SANITIZER.clean(html).to_string()
+}
+
+/// Renders the HTML as plain text, turning line breaks and paragraphs into newlines.
+pub fn to_plain_text(html: &str) -> String {
+    let html = LINE_BREAK_REGEX.replace_all(html, "\n");
+    let html = PARAGRAPH_END_REGEX.replace_all(&html, "\n\n");
+    let text = TEXT_SANITIZER.clean(&html).to_string();
+
+    // The sanitizer escapes the text it outputs
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_and_style_are_stripped_with_content() {
+        let html =
+            sanitize_html("<p>hi<script>alert(1)</script><style>p { color: red; }</style></p>");
+        assert_eq!(html, "<p>hi</p>");
+    }
+
+    #[test]
+    fn allowed_tags_are_kept() {
+        let html = "<p><strong>bold</strong> <em>em</em> <del>del</del><br><code>code</code></p>";
+        assert_eq!(sanitize_html(html), html);
+    }
+
+    #[test]
+    fn disallowed_tags_and_attributes_are_removed() {
+        let html = sanitize_html(r#"<p onclick="x()"><img src="x"><font>title</font></p>"#);
+        assert_eq!(html, "<p>title</p>");
+    }
+
+    #[test]
+    fn unknown_classes_are_removed() {
+        let html = sanitize_html(r#"<span class="mention evil">@alice</span>"#);
+        assert_eq!(html, r#"<span class="mention">@alice</span>"#);
+    }
+
+    #[test]
+    fn links_get_rel() {
+        let html = sanitize_html(r#"<a href="https://example.com/" rel="me">link</a>"#);
+        assert_eq!(
+            html,
+            r#"<a href="https://example.com/" rel="nofollow noopener noreferrer">link</a>"#
+        );
+    }
+
+    #[test]
+    fn unsafe_and_relative_links_are_dropped() {
+        let html = sanitize_html(r#"<a href="javascript:alert(1)">a</a><a href="/path">b</a>"#);
+        assert_eq!(
+            html,
+            r#"<a rel="nofollow noopener noreferrer">a</a><a rel="nofollow noopener noreferrer">b</a>"#
+        );
+    }
+
+    #[test]
+    fn plain_text_keeps_line_breaks() {
+        let text = to_plain_text("<p>a &amp; b<br>c</p><p>d<script>e</script></p>");
+        assert_eq!(text, "a & b\nc\n\nd");
+    }
+}
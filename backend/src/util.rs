@@ -1,30 +1,77 @@
 use sea_orm::{
     sea_query::{Expr, Func},
-    ConnectionTrait, EntityTrait, QuerySelect,
+    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect,
 };
 use url::Url;
 
 use crate::{
-    entity::{follower, user},
+    entity::{blocked_instance, follower, instance, relay, sea_orm_active_enums::Visibility, user},
     error::{Context, Result},
 };
 
 pub async fn get_follower_inboxes(db: &impl ConnectionTrait) -> Result<Vec<Url>> {
     let inboxes = follower::Entity::find()
+        .filter(follower::Column::Accepted.eq(true))
         .inner_join(user::Entity)
+        .filter(user::Column::GoneAt.is_null())
         .select_only()
+        .column(user::Column::Host)
         .expr(Func::coalesce([
             Expr::col(user::Column::SharedInbox).into(),
             Expr::col(user::Column::Inbox).into(),
         ]))
         .distinct()
+        .into_tuple::<(String, String)>()
+        .all(db)
+        .await
+        .context_internal_server_error("failed to query database")?;
+
+    let suspended_hosts = blocked_instance::Model::suspended_hosts(db).await?;
+    // Delivering to dead hosts only waits for timeouts, so they are skipped until they recover
+    let dead_hosts = instance::Model::dead_hosts(db).await?;
+    let inboxes = inboxes
+        .into_iter()
+        .filter(|(host, _)| !blocked_instance::Model::is_host_in(host, &suspended_hosts))
+        .filter_map(|(_, url)| Url::parse(&url).ok())
+        .filter(|url| url.host_str().is_none_or(|host| !dead_hosts.contains(host)))
+        .collect::<Vec<_>>();
+    Ok(inboxes)
+}
+
+pub async fn get_relay_inboxes(db: &impl ConnectionTrait) -> Result<Vec<Url>> {
+    let inboxes = relay::Entity::find()
+        .filter(relay::Column::Accepted.eq(true))
+        .select_only()
+        .column(relay::Column::Inbox)
         .into_tuple::<String>()
         .all(db)
         .await
         .context_internal_server_error("failed to query database")?;
+    let dead_hosts = instance::Model::dead_hosts(db).await?;
     let inboxes = inboxes
         .into_iter()
         .filter_map(|url| Url::parse(&url).ok())
+        .filter(|url| url.host_str().is_none_or(|host| !dead_hosts.contains(host)))
         .collect::<Vec<_>>();
     Ok(inboxes)
 }
+
+pub async fn get_post_inboxes(
+    visibility: &Visibility,
+    mention_user_uris: Vec<Url>,
+    db: &impl ConnectionTrait,
+) -> Result<Vec<Url>> {
+    match visibility {
+        Visibility::Public => {
+            let mut inboxes = get_follower_inboxes(db).await?;
+            for inbox in get_relay_inboxes(db).await? {
+                if !inboxes.contains(&inbox) {
+                    inboxes.push(inbox);
+                }
+            }
+            Ok(inboxes)
+        }
+        Visibility::Home | Visibility::Followers => get_follower_inboxes(db).await,
+        Visibility::DirectMessage => Ok(mention_user_uris),
+    }
+}
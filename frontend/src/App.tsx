@@ -3,10 +3,12 @@ import { BrowserRouter, Route, Routes } from "react-router-dom";
 
 import Layout from "./components/Layout";
 import { AccessKeyContextProvider } from "./contexts/auth";
+import EmojiPage from "./pages/Emoji";
 import IndexPage from "./pages/Index";
 import NotFoundPage from "./pages/NotFound";
 import NotePage from "./pages/Note";
 import PersonPage from "./pages/Person";
+import TagPage from "./pages/Tag";
 
 const queryClient = new QueryClient();
 
@@ -19,8 +21,10 @@ export default function App() {
             <Route path="/" element={<Layout />}>
               <Route errorElement={<NotFoundPage />}>
                 <Route index element={<IndexPage />} />
+                <Route path="emoji/:name" element={<EmojiPage />} />
                 <Route path="note/:id" element={<NotePage />} />
                 <Route path="person/" element={<PersonPage />} />
+                <Route path="tags/:name" element={<TagPage />} />
               </Route>
             </Route>
           </Routes>
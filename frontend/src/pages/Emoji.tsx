@@ -0,0 +1,17 @@
+import { useParams } from "react-router-dom";
+
+import NotFoundPage from "./NotFound";
+
+export default function EmojiPage() {
+  const { name } = useParams();
+
+  // The server puts the emoji image into the Open Graph metadata
+  const imageUrl = document
+    .querySelector('meta[property="og:image"]')
+    ?.getAttribute("content");
+  if (imageUrl == null) {
+    return <NotFoundPage />;
+  }
+
+  return <img src={imageUrl} alt={`:${name}:`} title={`:${name}:`} />;
+}
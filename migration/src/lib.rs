@@ -19,6 +19,23 @@ mod m20230813_081932_object_store_type;
 mod m20230814_150734_repost;
 mod m20230815_033104_notification;
 mod m20230824_155814_post_source;
+mod m20240525_101322_hide_follows;
+mod m20240527_143015_follow_request;
+mod m20240529_091244_post_revision;
+mod m20240601_112037_account_migration;
+mod m20240603_084512_blocked_instance;
+mod m20240605_133148_pinned_post;
+mod m20240607_101530_poll;
+mod m20240609_092417_emoji_import;
+mod m20240611_143022_activity;
+mod m20240613_101204_relay;
+mod m20240615_083317_user_gone;
+mod m20240617_140521_delivery;
+mod m20240619_092841_instance;
+mod m20240621_094215_poll_results_changed;
+mod m20240621_131406_activity_recipients;
+mod m20240622_081527_instance_software;
+mod m20240622_135210_sanitize_remote_html;
 
 pub struct Migrator;
 
@@ -45,6 +62,23 @@ impl MigratorTrait for Migrator {
             Box::new(m20230814_150734_repost::Migration),
             Box::new(m20230815_033104_notification::Migration),
             Box::new(m20230824_155814_post_source::Migration),
+            Box::new(m20240525_101322_hide_follows::Migration),
+            Box::new(m20240527_143015_follow_request::Migration),
+            Box::new(m20240529_091244_post_revision::Migration),
+            Box::new(m20240601_112037_account_migration::Migration),
+            Box::new(m20240603_084512_blocked_instance::Migration),
+            Box::new(m20240605_133148_pinned_post::Migration),
+            Box::new(m20240607_101530_poll::Migration),
+            Box::new(m20240609_092417_emoji_import::Migration),
+            Box::new(m20240611_143022_activity::Migration),
+            Box::new(m20240613_101204_relay::Migration),
+            Box::new(m20240615_083317_user_gone::Migration),
+            Box::new(m20240617_140521_delivery::Migration),
+            Box::new(m20240619_092841_instance::Migration),
+            Box::new(m20240621_094215_poll_results_changed::Migration),
+            Box::new(m20240621_131406_activity_recipients::Migration),
+            Box::new(m20240622_081527_instance_software::Migration),
+            Box::new(m20240622_135210_sanitize_remote_html::Migration),
         ]
     }
 }
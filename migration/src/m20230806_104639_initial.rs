@@ -222,6 +222,9 @@ pub enum User {
     ManuallyApprovesFollowers,
     IsBot,
     Description,
+    AlsoKnownAs,
+    MovedTo,
+    GoneAt,
 }
 
 #[derive(Iden)]
@@ -239,6 +242,8 @@ pub enum Post {
     RepostId,
     SourceContent,
     SourceMediaType,
+    UpdatedAt,
+    PinnedAt,
 }
 
 #[derive(Iden)]
@@ -266,10 +271,11 @@ enum Follow {
 }
 
 #[derive(Iden)]
-enum Follower {
+pub enum Follower {
     Table,
     FromId,
     Uri,
+    Accepted,
 }
 
 #[derive(Iden)]
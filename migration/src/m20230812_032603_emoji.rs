@@ -65,8 +65,10 @@ impl MigrationTrait for Migration {
 }
 
 #[derive(Iden)]
-enum Emoji {
+pub enum Emoji {
     Table,
     Name,
     CreatedAt,
+    OriginalUri,
+    License,
 }
@@ -79,4 +79,9 @@ pub enum Setting {
     MaintainerEmail,
     ThemeColor,
     UserDescription,
+    HideFollows,
+    ManuallyApprovesFollowers,
+    UserAlsoKnownAs,
+    UserMovedTo,
+    RemoteHtmlSanitizePending,
 }
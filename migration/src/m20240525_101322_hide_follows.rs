@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230812_135017_setting::Setting;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .add_column(
+                        ColumnDef::new(Setting::HideFollows)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .drop_column(Setting::HideFollows)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20230806_104639_initial::Follower, m20230812_135017_setting::Setting};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .add_column(
+                        ColumnDef::new(Setting::ManuallyApprovesFollowers)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Follower::Table)
+                    .add_column(
+                        ColumnDef::new(Follower::Accepted)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Follower::Table)
+                    .drop_column(Follower::Accepted)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .drop_column(Setting::ManuallyApprovesFollowers)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230806_104639_initial::Post;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Post::Table)
+                    .add_column(ColumnDef::new(Post::UpdatedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PostRevision::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PostRevision::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PostRevision::PostId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(PostRevision::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PostRevision::Text).string().not_null())
+                    .col(ColumnDef::new(PostRevision::Title).string())
+                    .col(
+                        ColumnDef::new(PostRevision::IsSensitive)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PostRevision::SourceContent).string())
+                    .col(ColumnDef::new(PostRevision::SourceMediaType).string())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PostRevision::Table, PostRevision::PostId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PostRevision::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Post::Table)
+                    .drop_column(Post::UpdatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum PostRevision {
+    Table,
+    Id,
+    PostId,
+    CreatedAt,
+    Text,
+    Title,
+    IsSensitive,
+    SourceContent,
+    SourceMediaType,
+}
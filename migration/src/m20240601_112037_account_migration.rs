@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20230806_104639_initial::User, m20230812_135017_setting::Setting};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .add_column(
+                        ColumnDef::new(User::AlsoKnownAs)
+                            .json_binary()
+                            .not_null()
+                            .default("[]"),
+                    )
+                    .add_column(ColumnDef::new(User::MovedTo).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .add_column(
+                        ColumnDef::new(Setting::UserAlsoKnownAs)
+                            .json_binary()
+                            .not_null()
+                            .default("[]"),
+                    )
+                    .add_column(ColumnDef::new(Setting::UserMovedTo).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .drop_column(Setting::UserAlsoKnownAs)
+                    .drop_column(Setting::UserMovedTo)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(User::Table)
+                    .drop_column(User::AlsoKnownAs)
+                    .drop_column(User::MovedTo)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_type(
+                Type::create()
+                    .as_enum(BlockSeverity::Table)
+                    .values([
+                        BlockSeverity::Suspend,
+                        BlockSeverity::RejectMedia,
+                        BlockSeverity::Silence,
+                    ])
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(BlockedInstance::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BlockedInstance::Host)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(BlockedInstance::Severity)
+                            .enumeration(
+                                BlockSeverity::Table,
+                                [
+                                    BlockSeverity::Suspend,
+                                    BlockSeverity::RejectMedia,
+                                    BlockSeverity::Silence,
+                                ],
+                            )
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BlockedInstance::Reason).string())
+                    .col(
+                        ColumnDef::new(BlockedInstance::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BlockedInstance::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_type(Type::drop().name(BlockSeverity::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum BlockedInstance {
+    Table,
+    Host,
+    Severity,
+    Reason,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum BlockSeverity {
+    Table,
+    Suspend,
+    RejectMedia,
+    Silence,
+}
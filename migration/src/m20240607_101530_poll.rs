@@ -0,0 +1,151 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230806_104639_initial::{Post, User};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Poll::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Poll::PostId).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Poll::EndTime).timestamp_with_time_zone())
+                    .col(ColumnDef::new(Poll::Multiple).boolean().not_null())
+                    .col(ColumnDef::new(Poll::VotersCount).integer())
+                    .col(
+                        ColumnDef::new(Poll::EndNotified)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Poll::Table, Poll::PostId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PollOption::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PollOption::PostId).uuid().not_null())
+                    .col(ColumnDef::new(PollOption::Order).small_integer().not_null())
+                    .col(ColumnDef::new(PollOption::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(PollOption::VotesCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PollOption::Table, PollOption::PostId)
+                            .to(Poll::Table, Poll::PostId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .index(
+                        Index::create()
+                            .col(PollOption::PostId)
+                            .col(PollOption::Order)
+                            .primary(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PollVote::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PollVote::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(PollVote::PostId).uuid().not_null())
+                    .col(ColumnDef::new(PollVote::Order).small_integer().not_null())
+                    .col(ColumnDef::new(PollVote::UserId).uuid())
+                    .col(
+                        ColumnDef::new(PollVote::Uri)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PollVote::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PollVote::Table, PollVote::PostId)
+                            .to(Poll::Table, Poll::PostId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(PollVote::Table, PollVote::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PollVote::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(PollOption::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Poll::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum Poll {
+    Table,
+    PostId,
+    EndTime,
+    Multiple,
+    VotersCount,
+    EndNotified,
+    ResultsChanged,
+}
+
+#[derive(Iden)]
+enum PollOption {
+    Table,
+    PostId,
+    Order,
+    Name,
+    VotesCount,
+}
+
+#[derive(Iden)]
+enum PollVote {
+    Table,
+    Id,
+    PostId,
+    Order,
+    UserId,
+    Uri,
+    CreatedAt,
+}
@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230812_032603_emoji::Emoji;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Emoji::Table)
+                    .add_column(ColumnDef::new(Emoji::OriginalUri).string())
+                    .add_column(ColumnDef::new(Emoji::License).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Emoji::Table)
+                    .drop_column(Emoji::OriginalUri)
+                    .drop_column(Emoji::License)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
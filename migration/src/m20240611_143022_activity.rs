@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230806_104639_initial::Post;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Activity::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Activity::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Activity::PostId).uuid())
+                    .col(ColumnDef::new(Activity::Data).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(Activity::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Activity::Table, Activity::PostId)
+                            .to(Post::Table, Post::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Activity::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum Activity {
+    Table,
+    Id,
+    PostId,
+    Data,
+    CreatedAt,
+    Recipients,
+}
@@ -0,0 +1,78 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Delivery::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Delivery::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Delivery::Inbox).string().not_null())
+                    .col(ColumnDef::new(Delivery::Data).json_binary().not_null())
+                    .col(
+                        ColumnDef::new(Delivery::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Delivery::NextAttemptAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Delivery::LastError).string())
+                    .col(
+                        ColumnDef::new(Delivery::Failed)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(Delivery::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_delivery_failed_next_attempt_at")
+                    .table(Delivery::Table)
+                    .col(Delivery::Failed)
+                    .col(Delivery::NextAttemptAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Delivery::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Delivery {
+    Table,
+    Id,
+    Inbox,
+    Data,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+    Failed,
+    CreatedAt,
+}
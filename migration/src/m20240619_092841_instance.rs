@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Instance::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Instance::Host)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Instance::LastDeliveredAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(Instance::LastFailedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(Instance::FailureCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(Instance::DeadSince).timestamp_with_time_zone())
+                    .col(ColumnDef::new(Instance::LastProbedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(Instance::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Instance::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+pub enum Instance {
+    Table,
+    Host,
+    LastDeliveredAt,
+    LastFailedAt,
+    FailureCount,
+    DeadSince,
+    LastProbedAt,
+    Software,
+    CreatedAt,
+}
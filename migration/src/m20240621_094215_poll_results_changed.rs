@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240607_101530_poll::Poll;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Poll::Table)
+                    .add_column(
+                        ColumnDef::new(Poll::ResultsChanged)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Poll::Table)
+                    .drop_column(Poll::ResultsChanged)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
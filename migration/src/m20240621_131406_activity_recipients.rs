@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240611_143022_activity::Activity;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Activity::Table)
+                    .add_column(
+                        ColumnDef::new(Activity::Recipients)
+                            .json_binary()
+                            .not_null()
+                            .default("[]"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Activity::Table)
+                    .drop_column(Activity::Recipients)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
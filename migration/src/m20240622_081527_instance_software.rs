@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20240619_092841_instance::Instance;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Instance::Table)
+                    .add_column(ColumnDef::new(Instance::Software).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Instance::Table)
+                    .drop_column(Instance::Software)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230812_135017_setting::Setting;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .add_column(
+                        ColumnDef::new(Setting::RemoteHtmlSanitizePending)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Remote HTML stored before this migration is sanitized by the backend on startup
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Setting::Table)
+                    .value(Setting::RemoteHtmlSanitizePending, true)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Setting::Table)
+                    .drop_column(Setting::RemoteHtmlSanitizePending)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}